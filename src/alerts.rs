@@ -0,0 +1,387 @@
+use crate::network::{NetworkStats, WebCheckStatus};
+use crate::webhook::WebhookConfig;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, warn};
+
+/// Alert settings stored in `Config`. Alerting is off until `enabled` is
+/// set; the default rules are there as an example to edit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    pub enabled: bool,
+    pub rules: Vec<AlertRule>,
+    /// Ring the terminal bell when an alert fires
+    pub bell: bool,
+    /// Shell command run for every alert event; details are passed in
+    /// `RPING_ALERT_*` environment variables
+    pub command: Option<String>,
+    /// HTTP endpoint receiving every alert event as JSON
+    pub webhook: Option<WebhookConfig>,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: vec![
+                AlertRule::Latency {
+                    above_ms: 200.0,
+                    for_secs: 10,
+                },
+                AlertRule::Loss { above_pct: 5.0 },
+                AlertRule::Outage,
+                AlertRule::Https,
+            ],
+            bell: false,
+            command: None,
+            webhook: None,
+        }
+    }
+}
+
+/// A condition that raises an alert while it holds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertRule {
+    /// Recent average latency above `above_ms` for at least `for_secs`
+    Latency { above_ms: f64, for_secs: u64 },
+    /// Packet loss in the graph window above `above_pct`
+    Loss { above_pct: f64 },
+    /// An outage started (and, once resolved, ended)
+    Outage,
+    /// The TCP 443 web check is failing
+    Https,
+}
+
+impl AlertRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertRule::Latency { .. } => "latency",
+            AlertRule::Loss { .. } => "loss",
+            AlertRule::Outage => "outage",
+            AlertRule::Https => "https",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+impl fmt::Display for AlertState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertState::Firing => write!(f, "firing"),
+            AlertState::Resolved => write!(f, "resolved"),
+        }
+    }
+}
+
+/// A rule changing state for one target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertEvent {
+    pub target: String,
+    pub rule: AlertRule,
+    pub state: AlertState,
+    pub message: String,
+    /// The measured value that triggered the event, if the rule has one
+    pub value: Option<f64>,
+    pub at: DateTime<Local>,
+}
+
+/// Per-target rule state, so every rule fires once and resolves once.
+#[derive(Debug, Default)]
+pub struct AlertTracker {
+    /// When each rule's condition started holding, indexed like `AlertConfig::rules`
+    since: Vec<Option<Instant>>,
+    firing: Vec<AlertEvent>,
+}
+
+impl AlertTracker {
+    /// Compare the latest statistics against every rule and return the
+    /// rules that started or stopped firing.
+    pub fn evaluate(
+        &mut self,
+        config: &AlertConfig,
+        target: &str,
+        stats: &NetworkStats,
+        now: Instant,
+    ) -> Vec<AlertEvent> {
+        if !config.enabled {
+            return self.clear();
+        }
+
+        self.since.resize(config.rules.len(), None);
+        let mut events = Vec::new();
+        for (i, rule) in config.rules.iter().enumerate() {
+            let (active, value, message) = check(rule, stats, now, &mut self.since[i]);
+            let index = self.firing.iter().position(|e| e.rule == *rule);
+            let event = match (active, index) {
+                (true, None) => {
+                    let event = AlertEvent {
+                        target: target.to_string(),
+                        rule: *rule,
+                        state: AlertState::Firing,
+                        message,
+                        value,
+                        at: Local::now(),
+                    };
+                    self.firing.push(event.clone());
+                    event
+                }
+                (false, Some(index)) => {
+                    let fired = self.firing.remove(index);
+                    AlertEvent {
+                        state: AlertState::Resolved,
+                        message: format!("{} resolved", fired.message),
+                        value,
+                        at: Local::now(),
+                        ..fired
+                    }
+                }
+                _ => continue,
+            };
+            events.push(event);
+        }
+        // Rules removed from the config can't resolve any more
+        self.firing.retain(|e| config.rules.contains(&e.rule));
+        events
+    }
+
+    /// Alerts currently firing, oldest first.
+    pub fn firing(&self) -> &[AlertEvent] {
+        &self.firing
+    }
+
+    /// Forget the state of every rule. Alerts still firing are returned as
+    /// resolved, so whoever was told they fired also hears they ended.
+    pub fn clear(&mut self) -> Vec<AlertEvent> {
+        self.since.clear();
+        self.firing
+            .drain(..)
+            .map(|fired| AlertEvent {
+                state: AlertState::Resolved,
+                message: format!("{} resolved", fired.message),
+                value: None,
+                at: Local::now(),
+                ..fired
+            })
+            .collect()
+    }
+}
+
+/// Whether `rule` currently holds, with the measured value and a description.
+fn check(
+    rule: &AlertRule,
+    stats: &NetworkStats,
+    now: Instant,
+    since: &mut Option<Instant>,
+) -> (bool, Option<f64>, String) {
+    match *rule {
+        AlertRule::Latency { above_ms, for_secs } => {
+            let latency = stats.current_avg;
+            if stats.current_response.is_some() && latency > above_ms {
+                let start = *since.get_or_insert(now);
+                let held = now.duration_since(start) >= Duration::from_secs(for_secs);
+                (
+                    held,
+                    Some(latency),
+                    format!("Latency {latency:.0} ms > {above_ms:.0} ms for {for_secs}s"),
+                )
+            } else {
+                *since = None;
+                (false, Some(latency), format!("Latency > {above_ms:.0} ms"))
+            }
+        }
+        AlertRule::Loss { above_pct } => {
+            let loss = stats.window.loss_pct;
+            (
+                loss > above_pct,
+                Some(loss),
+                format!("Packet loss {loss:.1}% > {above_pct:.1}%"),
+            )
+        }
+        AlertRule::Outage => {
+            let ongoing = stats.outages.ongoing;
+            (
+                ongoing.is_some(),
+                ongoing.map(|d| d.as_secs_f64()),
+                "Outage".to_string(),
+            )
+        }
+        AlertRule::Https => {
+            let failing = matches!(
+                stats.tcp_port_443,
+                WebCheckStatus::Timeout
+                    | WebCheckStatus::ConnectionRefused
+                    | WebCheckStatus::Error(_)
+            );
+            (failing, None, "TCP 443 check failing".to_string())
+        }
+    }
+}
+
+/// Ring the terminal bell.
+pub fn ring_bell() {
+    use std::io::Write;
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+}
+
+/// Run the user's alert command in the background with the event details in
+/// the environment.
+pub fn run_command(command: &str, event: &AlertEvent) {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    cmd.env("RPING_ALERT_TARGET", &event.target)
+        .env("RPING_ALERT_RULE", event.rule.name())
+        .env("RPING_ALERT_STATE", event.state.to_string())
+        .env("RPING_ALERT_MESSAGE", &event.message)
+        .env(
+            "RPING_ALERT_VALUE",
+            event.value.map(|v| format!("{v:.2}")).unwrap_or_default(),
+        )
+        .env("RPING_ALERT_TIME", event.at.to_rfc3339())
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+
+    match cmd.spawn() {
+        Ok(mut child) => {
+            let command = command.to_string();
+            tokio::spawn(async move {
+                match child.wait().await {
+                    Ok(status) if status.success() => debug!("Alert command finished"),
+                    Ok(status) => warn!("Alert command '{}' exited with {}", command, status),
+                    Err(e) => error!("Alert command '{}' failed: {}", command, e),
+                }
+            });
+        }
+        Err(e) => error!("Failed to run alert command '{}': {}", command, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{OutageSummary, SpanStats};
+
+    #[test]
+    fn test_rules_fire_once_and_resolve() {
+        let config = AlertConfig {
+            rules: vec![
+                AlertRule::Latency {
+                    above_ms: 100.0,
+                    for_secs: 5,
+                },
+                AlertRule::Outage,
+            ],
+            enabled: true,
+            ..AlertConfig::default()
+        };
+        assert!(!AlertConfig::default().enabled);
+        let mut tracker = AlertTracker::default();
+        let t0 = Instant::now();
+        let slow = NetworkStats {
+            current_response: Some(250.0),
+            current_avg: 250.0,
+            ..NetworkStats::default()
+        };
+
+        // Latency must stay high for the whole hold time
+        assert!(tracker.evaluate(&config, "a", &slow, t0).is_empty());
+        let events = tracker.evaluate(&config, "a", &slow, t0 + Duration::from_secs(6));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].state, AlertState::Firing);
+        assert!(tracker
+            .evaluate(&config, "a", &slow, t0 + Duration::from_secs(7))
+            .is_empty());
+
+        let down = NetworkStats {
+            outages: OutageSummary {
+                count: 1,
+                ongoing: Some(Duration::from_secs(3)),
+                ..OutageSummary::default()
+            },
+            window: SpanStats {
+                sent: 10,
+                ..SpanStats::default()
+            },
+            ..NetworkStats::default()
+        };
+        let events = tracker.evaluate(&config, "a", &down, t0 + Duration::from_secs(8));
+        let states: Vec<_> = events.iter().map(|e| (e.rule.name(), e.state)).collect();
+        assert_eq!(
+            states,
+            [
+                ("latency", AlertState::Resolved),
+                ("outage", AlertState::Firing)
+            ]
+        );
+        assert_eq!(tracker.firing().len(), 1);
+
+        // A reset resolves what was still firing
+        let events = tracker.clear();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].rule, AlertRule::Outage);
+        assert_eq!(events[0].state, AlertState::Resolved);
+        assert!(tracker.firing().is_empty());
+        assert!(tracker.clear().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_receives_event_env() {
+        let out = std::env::temp_dir().join(format!("rping-alert-{}.txt", std::process::id()));
+        let command = format!(
+            "printf '%s %s %s' \"$RPING_ALERT_TARGET\" \"$RPING_ALERT_RULE\" \"$RPING_ALERT_STATE\" > {}",
+            out.display()
+        );
+        let event = AlertEvent {
+            target: "example.com".to_string(),
+            rule: AlertRule::Outage,
+            state: AlertState::Firing,
+            message: "Outage".to_string(),
+            value: Some(3.0),
+            at: Local::now(),
+        };
+        run_command(&command, &event);
+
+        let mut written = String::new();
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            written = std::fs::read_to_string(&out).unwrap_or_default();
+            if !written.is_empty() {
+                break;
+            }
+        }
+        let _ = std::fs::remove_file(&out);
+        assert_eq!(written, "example.com outage firing");
+    }
+
+    #[test]
+    fn test_rules_deserialize() {
+        let json = r#"{"rules": [{"kind": "loss", "above_pct": 2.5}], "bell": true}"#;
+        let config: AlertConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.rules, [AlertRule::Loss { above_pct: 2.5 }]);
+        // Configs that don't turn alerting on keep it off
+        assert!(config.bell && !config.enabled);
+        assert_eq!(config.command, None);
+    }
+}
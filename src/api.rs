@@ -0,0 +1,411 @@
+use crate::app::{App, TargetSession};
+use crate::http::{self, Request, Response};
+use crate::network::{NetworkStats, PathMtu, SpeedTestState, WebCheckStatus};
+use crate::probe_log::probe_name;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+
+/// A request waiting for the UI loop to answer it.
+type Pending = (Request, oneshot::Sender<Response>);
+
+const ENDPOINTS: &[(&str, &str, &str)] = &[
+    ("GET", "/api/targets", "Monitored targets"),
+    (
+        "GET",
+        "/api/stats",
+        "Current statistics per target (?target=NAME for one)",
+    ),
+    (
+        "GET",
+        "/api/latency",
+        "RTT samples in the graph window, null for timeouts (?target=NAME)",
+    ),
+    ("GET", "/api/portscan", "State and results of the port scan"),
+    (
+        "GET",
+        "/api/speedtest",
+        "State and results of the speed test",
+    ),
+    ("POST", "/api/speedtest", "Start a speed test"),
+    ("POST", "/api/reset", "Reset the statistics of every target"),
+];
+
+/// The `--api` endpoint. Requests are handed to the UI loop, which answers
+/// them from the live state on its next tick.
+pub struct ApiServer {
+    rx: mpsc::Receiver<Pending>,
+    addr: SocketAddr,
+}
+
+impl ApiServer {
+    pub async fn start(addr: SocketAddr) -> Result<Self> {
+        let (tx, rx) = mpsc::channel::<Pending>(32);
+        let addr = http::serve(addr, "API", move |request| {
+            let tx = tx.clone();
+            async move {
+                // Another origin means a web page is trying to reach the API
+                // through the user's browser
+                if request.method == "POST" && request.header("origin").is_some() {
+                    return error(403, "Cross-origin requests are not allowed");
+                }
+                let (reply, response) = oneshot::channel();
+                if tx.try_send((request, reply)).is_err() {
+                    return error(503, "Too many requests");
+                }
+                response
+                    .await
+                    .unwrap_or_else(|_| error(503, "rping is shutting down"))
+            }
+        })
+        .await?;
+        if !addr.ip().is_loopback() {
+            warn!("The API on {} can be reached from other hosts", addr);
+        }
+        Ok(Self { rx, addr })
+    }
+
+    /// Where the API listens, for the startup message.
+    pub fn describe(&self) -> String {
+        format!("Serving the JSON API on http://{}/api", self.addr)
+    }
+
+    /// Requests received since the last call.
+    pub fn pending(&mut self) -> Vec<Pending> {
+        let mut pending = Vec::new();
+        while let Ok(request) = self.rx.try_recv() {
+            pending.push(request);
+        }
+        pending
+    }
+}
+
+fn json_response(status: u16, value: Value) -> Response {
+    Response::new(status, "application/json", format!("{value}\n"))
+}
+
+fn error(status: u16, message: &str) -> Response {
+    json_response(status, json!({ "error": message }))
+}
+
+/// Answer one API request from the current state of `app`.
+pub async fn respond(app: &mut App, request: &Request) -> Response {
+    let method = if request.method == "HEAD" {
+        "GET"
+    } else {
+        request.method.as_str()
+    };
+    match (method, request.path.trim_end_matches('/')) {
+        ("GET", "/api") => json_response(
+            200,
+            Value::Array(
+                ENDPOINTS
+                    .iter()
+                    .map(|(method, path, description)| {
+                        json!({ "method": method, "path": path, "description": description })
+                    })
+                    .collect(),
+            ),
+        ),
+        ("GET", "/api/targets") => json_response(
+            200,
+            app.targets
+                .iter()
+                .map(|s| {
+                    json!({
+                        "target": s.target,
+                        "ip": s.ping_monitor.get_target_addr().to_string(),
+                        "probe": probe_name(s.ping_monitor.probe_method),
+                    })
+                })
+                .collect(),
+        ),
+        ("GET", "/api/stats") => match selected(app, request) {
+            Ok(sessions) => json_response(
+                200,
+                sessions
+                    .iter()
+                    .map(|s| {
+                        let mut stats = stats_json(&s.stats);
+                        stats["target"] = json!(s.target);
+                        stats
+                    })
+                    .collect(),
+            ),
+            Err(response) => response,
+        },
+        ("GET", "/api/latency") => match selected(app, request) {
+            Ok(sessions) => json_response(
+                200,
+                sessions
+                    .iter()
+                    .map(|s| {
+                        json!({
+                            "target": s.target,
+                            "interval_ms": app.config.ping_interval_ms,
+                            "samples": s.ping_monitor.latency_data(),
+                        })
+                    })
+                    .collect(),
+            ),
+            Err(response) => response,
+        },
+        ("GET", "/api/portscan") => json_response(200, portscan_json(app)),
+        ("GET", "/api/speedtest") => json_response(
+            200,
+            app.speedtest
+                .as_ref()
+                .map_or_else(|| json!({ "state": "idle" }), |st| speedtest_json(st.get_state())),
+        ),
+        ("POST", "/api/speedtest") => {
+            if app.speedtest.as_ref().is_some_and(|st| !st.is_complete()) {
+                return error(409, "A speed test is already running");
+            }
+            info!("Speed test requested through the API");
+            app.speedtest = None;
+            match app.start_speedtest().await {
+                Ok(()) => json_response(202, json!({ "state": "preparing" })),
+                Err(e) => error(500, &e.to_string()),
+            }
+        }
+        ("POST", "/api/reset") => {
+            info!("Statistics reset requested through the API");
+            app.reset_stats();
+            json_response(200, json!({ "reset": true }))
+        }
+        (_, path) if ENDPOINTS.iter().any(|(_, p, _)| *p == path) || path == "/api" => {
+            error(405, "Method not allowed")
+        }
+        _ => error(404, "Not found"),
+    }
+}
+
+/// The sessions named by `?target=`, or all of them.
+fn selected<'a>(app: &'a App, request: &Request) -> Result<Vec<&'a TargetSession>, Response> {
+    match request.param("target") {
+        None => Ok(app.targets.iter().collect()),
+        Some(target) => match app.targets.iter().find(|s| s.target == *target) {
+            Some(session) => Ok(vec![session]),
+            None => Err(error(404, &format!("{target} is not being monitored"))),
+        },
+    }
+}
+
+fn web_check_json(status: &WebCheckStatus) -> Value {
+    match status {
+        WebCheckStatus::Untested => json!({ "status": "untested" }),
+        WebCheckStatus::Success(ms) => json!({ "status": "ok", "connect_ms": ms }),
+        WebCheckStatus::Timeout => json!({ "status": "timeout" }),
+        WebCheckStatus::ConnectionRefused => json!({ "status": "refused" }),
+        WebCheckStatus::Error(e) => json!({ "status": "error", "error": e }),
+    }
+}
+
+fn stats_json(stats: &NetworkStats) -> Value {
+    let span = |s: &crate::network::SpanStats| {
+        json!({
+            "sent": s.sent,
+            "received": s.received,
+            "loss_pct": s.loss_pct,
+            "avg_ms": s.avg_ms,
+            "min_ms": s.min_ms,
+            "max_ms": s.max_ms,
+            "std_dev_ms": s.std_dev,
+        })
+    };
+    let path_mtu = match &stats.path_mtu {
+        PathMtu::Found { mtu, .. } => json!(mtu),
+        _ => Value::Null,
+    };
+    json!({
+        "quality": stats.quality,
+        "current_ms": stats.current_response,
+        "current_avg_ms": stats.current_avg,
+        "lifetime": span(&stats.lifetime),
+        "window": span(&stats.window),
+        "jitter_ms": stats.jitter,
+        "mean_delta_ms": stats.mean_delta,
+        "percentiles_ms": {
+            "p50": stats.percentiles.p50,
+            "p90": stats.percentiles.p90,
+            "p95": stats.percentiles.p95,
+            "p99": stats.percentiles.p99,
+        },
+        "r_factor": stats.r_factor,
+        "mos": stats.mos,
+        "stability": stats.stability,
+        "ttl": stats.ttl,
+        "reply_size": stats.reply_size,
+        "route_changes": stats.route_changes,
+        "foreign_replies": stats.foreign_replies,
+        "late_replies": stats.late_replies,
+        "duplicate_replies": stats.duplicate_replies,
+        "reordered_replies": stats.reordered_replies,
+        "outages": {
+            "count": stats.outages.count,
+            "longest_secs": stats.outages.longest.as_secs_f64(),
+            "mttr_secs": stats.outages.mttr.map(|d| d.as_secs_f64()),
+            "ongoing_secs": stats.outages.ongoing.map(|d| d.as_secs_f64()),
+        },
+        "dns_ms": stats.dns_duration,
+        "path_mtu": path_mtu,
+        "web_checks": {
+            "80": web_check_json(&stats.tcp_port_80),
+            "443": web_check_json(&stats.tcp_port_443),
+        },
+    })
+}
+
+fn portscan_json(app: &App) -> Value {
+    let Some(scan) = &app.portscan else {
+        return json!({ "state": "idle" });
+    };
+    let (done, total) = scan.progress();
+    json!({
+        "state": if scan.is_complete() { "complete" } else { "running" },
+        "target": scan.target(),
+        "ip": scan.target_ip().to_string(),
+        "progress": { "done": done, "total": total },
+        "results": scan
+            .results()
+            .iter()
+            .map(|r| json!({ "port": r.port, "status": r.status, "service": r.service }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn speedtest_json(state: &SpeedTestState) -> Value {
+    match state {
+        SpeedTestState::Preparing => json!({ "state": "preparing" }),
+        SpeedTestState::Downloading { bytes_received, .. } => {
+            json!({ "state": "downloading", "bytes": bytes_received })
+        }
+        SpeedTestState::Uploading {
+            bytes_sent,
+            download_results,
+        } => json!({
+            "state": "uploading",
+            "bytes": bytes_sent,
+            "download_mbps": download_results.0,
+        }),
+        SpeedTestState::Complete {
+            download_mbps,
+            upload_mbps,
+            total_bytes,
+            duration,
+            peak_speed,
+            ..
+        } => json!({
+            "state": "complete",
+            "download_mbps": download_mbps,
+            "upload_mbps": upload_mbps,
+            "peak_mbps": peak_speed,
+            "total_bytes": total_bytes,
+            "duration_secs": duration.as_secs_f64(),
+        }),
+        SpeedTestState::Error(e) => json!({ "state": "error", "error": e }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::Player;
+    use crate::storage::Config;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    /// Send a request and keep the app ticking until the answer arrives.
+    async fn call(app: &mut App, addr: SocketAddr, request: &str) -> (u16, Value) {
+        let request = request.to_string();
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        });
+        while !client.is_finished() {
+            app.tick().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let response = client.await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head[9..12].parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_read_and_reset() {
+        let path = std::env::temp_dir().join(format!("rping-api-{}.jsonl", std::process::id()));
+        let mut lines = vec![
+            r#"{"k":"session","version":"2.7.0","started":"2024-05-01T03:12:00+00:00","interval_ms":1000,"history_length":100}"#.to_string(),
+            r#"{"k":"target","t":0,"id":0,"target":"example.com","addr":"192.0.2.1","probe":"Icmp"}"#.to_string(),
+            r#"{"k":"target","t":0,"id":1,"target":"2001:db8::1","addr":"2001:db8::1","probe":"Icmp"}"#.to_string(),
+        ];
+        for seq in 1..=4u64 {
+            lines.push(format!(
+                r#"{{"k":"reply","t":{},"id":0,"seq":{seq},"rtt":{seq}.5,"size":16,"src":"192.0.2.1"}}"#,
+                seq * 1000
+            ));
+        }
+        lines.push(r#"{"k":"timeout","t":5000,"id":0,"seq":5}"#.to_string());
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let player = Player::open(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let mut app = App::replay(player, &Config::default(), true);
+        app.replay_seek_to(Duration::from_secs(5));
+        app.replay_toggle_pause();
+
+        let api = ApiServer::start("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let addr = api.addr;
+        app.api = Some(api);
+
+        let (status, stats) = call(&mut app, addr, "GET /api/stats HTTP/1.1\r\n\r\n").await;
+        assert_eq!(status, 200);
+        assert_eq!(stats[0]["target"], "example.com");
+        assert_eq!(stats[0]["lifetime"]["sent"], 5);
+        assert_eq!(stats[0]["lifetime"]["received"], 4);
+
+        let request = "GET /api/latency?target=example.com HTTP/1.1\r\n\r\n";
+        let (_, latency) = call(&mut app, addr, request).await;
+        assert_eq!(
+            latency[0]["samples"],
+            serde_json::json!([1.5, 2.5, 3.5, 4.5, null])
+        );
+
+        let (status, _) = call(
+            &mut app,
+            addr,
+            "GET /api/latency?target=nope HTTP/1.1\r\n\r\n",
+        )
+        .await;
+        assert_eq!(status, 404);
+        let request = "GET /api/stats?target=2001%3Adb8%3A%3A1 HTTP/1.1\r\n\r\n";
+        let (status, stats) = call(&mut app, addr, request).await;
+        assert_eq!(status, 200);
+        assert_eq!(stats.as_array().unwrap().len(), 1);
+        assert_eq!(stats[0]["target"], "2001:db8::1");
+        let (_, speedtest) = call(&mut app, addr, "GET /api/speedtest HTTP/1.1\r\n\r\n").await;
+        assert_eq!(speedtest["state"], "idle");
+
+        // A web page must not be able to trigger actions through the browser
+        let request = "POST /api/reset HTTP/1.1\r\nOrigin: http://evil.example\r\n\r\n";
+        assert_eq!(call(&mut app, addr, request).await.0, 403);
+        assert_eq!(
+            call(&mut app, addr, "DELETE /api/reset HTTP/1.1\r\n\r\n")
+                .await
+                .0,
+            405
+        );
+
+        let (status, _) = call(&mut app, addr, "POST /api/reset HTTP/1.1\r\n\r\n").await;
+        assert_eq!(status, 200);
+        assert_eq!(app.ping_monitor().stats().lifetime.sent, 0);
+    }
+}
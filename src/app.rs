@@ -1,14 +1,23 @@
+use crate::alerts::{self, AlertEvent, AlertState, AlertTracker};
+use crate::api::{self, ApiServer};
+use crate::metrics::Metrics;
 use crate::network::{
-    start_ping_task, PingCommand, PingMonitor, PingResult, PortScanner, SpeedTest,
+    discover_path_mtu, start_ping_task, NetworkStats, PathMtu, PingCommand, PingMonitor,
+    PingResult, PortScanner, ProbeMethod, SpeedTest, Traceroute,
 };
-use crate::storage::{Config, TargetHistory};
+use crate::probe_log::ProbeLog;
+use crate::recording::Recorder;
+use crate::replay::Player;
+use crate::sink::Sink;
+use crate::storage::Config;
+use crate::webhook::WebhookNotifier;
 use anyhow::Result;
-use chrono::Local;
-use std::fs::{File, OpenOptions};
-use std::io::{BufWriter, Write};
+use chrono::{DateTime, Local};
+use std::net::IpAddr;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
-use tracing::{debug, error, info, trace};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, trace, warn};
 
 use crate::theme::Theme;
 
@@ -20,22 +29,133 @@ pub enum AppTab {
     Settings,
 }
 
-pub struct App {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AppView {
+    /// Graph and statistics for the selected target
+    Detail,
+    /// Compact table of every monitored target
+    Overview,
+    /// MTR-style per-hop statistics for the selected target
+    Trace,
+}
+
+/// A single monitored host with its own background ping task and statistics.
+pub struct TargetSession {
     pub target: String,
     pub ping_monitor: PingMonitor,
-    pub start_time: Instant,
+    /// Statistics snapshot taken once per tick, shared by every panel drawn
+    pub stats: NetworkStats,
+    pub alerts: AlertTracker,
 
     // Background Task Channels
     pub ping_tx: mpsc::Sender<PingCommand>,
     pub ping_rx: mpsc::Receiver<PingResult>,
+    /// Running path MTU discovery, if any
+    mtu_task: Option<JoinHandle<Result<PathMtu>>>,
+}
+
+impl TargetSession {
+    pub async fn start(target: String, config: &Config) -> Result<Self> {
+        debug!("Starting background ping task for {}...", target);
+        let task = start_ping_task(
+            &target,
+            config.ping_interval_ms,
+            config.probe_method,
+            config.probe_options(),
+        )
+        .await?;
+        info!(
+            "Ping task started. Target addr: {}, DNS duration: {:?}, probe: {}",
+            task.target_addr, task.dns_duration, task.probe_method
+        );
+
+        let mut ping_monitor = PingMonitor::new(task.target_addr, config.graph_history_length);
+        ping_monitor.dns_duration = task.dns_duration;
+        ping_monitor.requested_probe_method = config.probe_method;
+        ping_monitor.probe_method = task.probe_method;
+        ping_monitor.outages.threshold = config.outage_threshold.max(1);
+
+        Ok(Self {
+            target,
+            stats: ping_monitor.stats(),
+            alerts: AlertTracker::default(),
+            ping_monitor,
+            ping_tx: task.cmd_tx,
+            ping_rx: task.res_rx,
+            mtu_task: None,
+        })
+    }
+
+    /// A session fed from a recording instead of a background ping task.
+    pub fn replay(target: String, addr: IpAddr, probe: ProbeMethod, config: &Config) -> Self {
+        let mut ping_monitor = PingMonitor::new(addr, config.graph_history_length);
+        ping_monitor.requested_probe_method = probe;
+        ping_monitor.probe_method = probe;
+        ping_monitor.outages.threshold = config.outage_threshold.max(1);
+        // Nothing listens for commands and nothing sends results
+        let (ping_tx, _) = mpsc::channel(1);
+        let (_, ping_rx) = mpsc::channel(1);
+
+        Self {
+            target,
+            stats: ping_monitor.stats(),
+            alerts: AlertTracker::default(),
+            ping_monitor,
+            ping_tx,
+            ping_rx,
+            mtu_task: None,
+        }
+    }
+
+    /// Start path MTU discovery in the background unless it is already running.
+    pub fn start_mtu_discovery(&mut self, timeout: Duration) {
+        if self.mtu_task.is_some() {
+            return;
+        }
+        let addr = self.ping_monitor.get_target_addr();
+        info!("Starting path MTU discovery for {}", addr);
+        self.ping_monitor.path_mtu = PathMtu::Discovering;
+        self.mtu_task = Some(tokio::spawn(discover_path_mtu(addr, timeout)));
+    }
+
+    /// Store the discovery result once the background task has finished.
+    async fn poll_mtu_discovery(&mut self) {
+        if !self.mtu_task.as_ref().is_some_and(|t| t.is_finished()) {
+            return;
+        }
+        let Some(task) = self.mtu_task.take() else {
+            return;
+        };
+        self.ping_monitor.path_mtu = match task.await {
+            Ok(Ok(mtu)) => mtu,
+            Ok(Err(e)) => {
+                error!("Path MTU discovery for {} failed: {}", self.target, e);
+                PathMtu::Failed(e.to_string())
+            }
+            Err(e) => PathMtu::Failed(e.to_string()),
+        };
+    }
+}
+
+pub struct App {
+    pub targets: Vec<TargetSession>,
+    pub selected_target: usize,
+    pub start_time: Instant,
 
     // Logging
-    pub log_writer: Option<BufWriter<File>>,
+    pub log: Option<ProbeLog>,
+    pub recorder: Option<Recorder>,
+    pub metrics: Option<Metrics>,
+    /// InfluxDB and StatsD outputs
+    pub sinks: Vec<Sink>,
+    /// The `--api` server, answered from `tick`
+    pub api: Option<ApiServer>,
 
     // UI State
     pub theme: Theme,
     #[allow(dead_code)]
     pub current_tab: AppTab,
+    pub view: AppView,
     pub show_settings: bool,
     pub show_diagnostics: bool,
     pub show_jitter: bool,
@@ -43,53 +163,103 @@ pub struct App {
     pub enable_web_check: bool,
     pub settings_selected: usize,
 
+    // Add-target prompt
+    pub show_add_target: bool,
+    pub add_target_input: String,
+    pub add_target_error: Option<String>,
+    /// Target being resolved and started in the background
+    adding_target: Option<(String, JoinHandle<Result<TargetSession>>)>,
+
+    // Alert delivery
+    webhook: Option<WebhookNotifier>,
+
+    /// Playback of a recorded session, in place of live probes
+    pub replay: Option<Player>,
+
     // Features
     pub speedtest: Option<SpeedTest>,
     pub portscan: Option<PortScanner>,
+    pub traceroute: Option<Traceroute>,
 
     // Config
     pub config: Config,
 }
 
 impl App {
-    pub async fn new(target: String, log_file: Option<String>, monotone: bool) -> Result<Self> {
-        info!("Initializing App for target: {}", target);
-        let history = TargetHistory::load()?;
-        let config = history.config.clone();
-        debug!("Loaded configuration: {:?}", config);
-
-        // Start background ping task
-        debug!("Starting background ping task...");
-        let (target_addr, ping_tx, ping_rx, dns_duration) =
-            start_ping_task(&target, config.ping_interval_ms).await?;
-        info!(
-            "Ping task started. Target addr: {}, DNS duration: {:?}",
-            target_addr, dns_duration
-        );
+    pub async fn new(
+        targets: Vec<String>,
+        config: Config,
+        log: Option<ProbeLog>,
+        metrics: Option<Metrics>,
+        sinks: Vec<Sink>,
+        api: Option<ApiServer>,
+        monotone: bool,
+    ) -> Result<Self> {
+        info!("Initializing App for targets: {:?}", targets);
+        if targets.is_empty() {
+            anyhow::bail!("At least one target is required");
+        }
+        debug!("Using configuration: {:?}", config);
 
-        let mut ping_monitor = PingMonitor::new(target_addr, config.graph_history_length);
-        ping_monitor.dns_duration = dns_duration;
-
-        // Initialize logger if requested
-        let log_writer = if let Some(path) = log_file {
-            debug!("Initializing CSV log writer at: {}", path);
-            let file = OpenOptions::new().create(true).append(true).open(&path)?;
-            let mut writer = BufWriter::new(file);
-            // Write header if file is empty
-            if std::fs::metadata(&path)?.len() == 0 {
-                writeln!(writer, "Timestamp,Target,Latency(ms),Status")?;
-            }
-            Some(writer)
+        // Start one background ping task per target
+        let mut sessions = Vec::with_capacity(targets.len());
+        for target in targets {
+            sessions.push(TargetSession::start(target, &config).await?);
+        }
+
+        let mut recorder = if config.record_sessions {
+            Recorder::create(&config)
+                .map_err(|e| warn!("Session recording disabled: {}", e))
+                .ok()
         } else {
             None
         };
+        if let Some(recorder) = &mut recorder {
+            for session in &sessions {
+                recorder.add_target(
+                    &session.target,
+                    session.ping_monitor.get_target_addr(),
+                    session.ping_monitor.probe_method,
+                );
+            }
+        }
+
+        let webhook = match config.alerts.webhook.clone() {
+            Some(webhook) if !webhook.url.is_empty() => Some(WebhookNotifier::start(webhook)?),
+            _ => None,
+        };
 
         Ok(Self {
-            target,
-            ping_monitor,
-            ping_tx,
-            ping_rx,
-            log_writer,
+            log,
+            metrics,
+            sinks,
+            api,
+            recorder,
+            webhook,
+            ..Self::with_sessions(sessions, config, monotone)
+        })
+    }
+
+    /// Play back a recorded session instead of probing live.
+    pub fn replay(player: Player, config: &Config, monotone: bool) -> Self {
+        let config = player.config(config);
+        info!("Replaying {}", player.file());
+        let sessions = player.sessions(&config);
+        Self {
+            replay: Some(player),
+            ..Self::with_sessions(sessions, config, monotone)
+        }
+    }
+
+    fn with_sessions(sessions: Vec<TargetSession>, config: Config, monotone: bool) -> Self {
+        Self {
+            targets: sessions,
+            selected_target: 0,
+            log: None,
+            metrics: None,
+            sinks: Vec::new(),
+            api: None,
+            recorder: None,
             start_time: Instant::now(),
             theme: if monotone {
                 Theme::monotone()
@@ -97,16 +267,71 @@ impl App {
                 Theme::blacksite()
             },
             current_tab: AppTab::Monitor,
+            view: AppView::Detail,
             show_settings: false,
             show_diagnostics: false,
             show_jitter: config.show_jitter_panel,
             show_history: config.show_history_panel,
             enable_web_check: false,
             settings_selected: 0,
+            show_add_target: false,
+            add_target_input: String::new(),
+            add_target_error: None,
+            adding_target: None,
             speedtest: None,
             portscan: None,
+            traceroute: None,
+            webhook: None,
+            replay: None,
             config,
-        })
+        }
+    }
+
+    /// The target currently shown in the detail view.
+    pub fn current(&self) -> &TargetSession {
+        &self.targets[self.selected_target]
+    }
+
+    /// Shorthand for the selected target's hostname or IP.
+    pub fn target(&self) -> &str {
+        &self.current().target
+    }
+
+    /// Shorthand for the selected target's monitor.
+    pub fn ping_monitor(&self) -> &PingMonitor {
+        &self.current().ping_monitor
+    }
+
+    /// Alerts currently firing on any target.
+    pub fn firing_alerts(&self) -> impl Iterator<Item = &AlertEvent> {
+        self.targets.iter().flat_map(|s| s.alerts.firing())
+    }
+
+    fn dispatch_alert(&self, event: &AlertEvent) {
+        match event.state {
+            AlertState::Firing => warn!("Alert for {}: {}", event.target, event.message),
+            AlertState::Resolved => info!("Alert for {}: {}", event.target, event.message),
+        }
+        let config = &self.config.alerts;
+        if config.bell && event.state == AlertState::Firing {
+            alerts::ring_bell();
+        }
+        if let Some(command) = &config.command {
+            alerts::run_command(command, event);
+        }
+        if let Some(webhook) = &self.webhook {
+            webhook.notify(event);
+        }
+    }
+
+    /// Cached statistics of the selected target, refreshed every tick.
+    pub fn stats(&self) -> &NetworkStats {
+        &self.current().stats
+    }
+
+    /// True when no overlay or panel is capturing the main shortcuts.
+    pub fn shortcuts_enabled(&self) -> bool {
+        !self.show_settings && self.speedtest.is_none() && self.portscan.is_none()
     }
 
     pub async fn tick(&mut self) -> Result<()> {
@@ -118,29 +343,71 @@ impl App {
             self.portscan.is_some()
         );
 
-        // Process incoming ping results
+        if let Some(mut player) = self.replay.take() {
+            player.advance(self);
+            self.replay = Some(player);
+        }
+        let now = self.now();
+        self.poll_add_target().await;
+
+        // Process incoming ping results for every target
         let mut processed_count = 0;
-        while let Ok(result) = self.ping_rx.try_recv() {
-            processed_count += 1;
-            trace!("Received ping result: {:?}", result);
-            // Log result if enabled
-            if let Some(writer) = &mut self.log_writer {
-                let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
-                let (ms, status) = match result {
-                    PingResult::Success(v) => (v, "Success"),
-                    PingResult::Timeout => (0.0, "Timeout"),
-                    PingResult::WebCheck { .. } => (0.0, "WebCheck"), // Skip logging detailed web stats for now
-                };
-                if status != "WebCheck" {
-                    if let Err(e) =
-                        writeln!(writer, "{},{},{:.2},{}", timestamp, self.target, ms, status)
-                    {
-                        error!("Failed to write to CSV log: {}", e);
-                    }
+        let mut alert_events = Vec::new();
+        for (index, session) in self.targets.iter_mut().enumerate() {
+            while let Ok(result) = session.ping_rx.try_recv() {
+                processed_count += 1;
+                trace!("Received ping result for {}: {:?}", session.target, result);
+                if let Some(log) = &mut self.log {
+                    log.ping(
+                        &session.target,
+                        session.ping_monitor.get_target_addr(),
+                        session.ping_monitor.probe_method,
+                        &result,
+                    );
+                }
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.ping(index, &result);
+                }
+                for sink in &mut self.sinks {
+                    sink.ping(
+                        &session.target,
+                        session.ping_monitor.get_target_addr(),
+                        session.ping_monitor.probe_method,
+                        &result,
+                    );
+                }
+                if let Some(metrics) = &self.metrics {
+                    metrics.ping(
+                        &session.target,
+                        session.ping_monitor.get_target_addr(),
+                        session.ping_monitor.probe_method,
+                        &result,
+                    );
                 }
+                session.ping_monitor.process_result(result);
             }
-
-            self.ping_monitor.process_result(result);
+            session.poll_mtu_discovery().await;
+            session.stats = session.ping_monitor.stats_at(now);
+            if let Some(metrics) = &self.metrics {
+                metrics.stats(&session.target, &session.stats);
+            }
+            let events = session.alerts.evaluate(
+                &self.config.alerts,
+                &session.target,
+                &session.stats,
+                Instant::now(),
+            );
+            alert_events.extend(events);
+        }
+        for event in &alert_events {
+            self.dispatch_alert(event);
+        }
+        for sink in &mut self.sinks {
+            sink.stats(
+                self.targets
+                    .iter()
+                    .map(|s| (s.target.as_str(), s.stats.clone())),
+            );
         }
         if processed_count > 0 {
             trace!("Processed {} ping results in this tick", processed_count);
@@ -148,16 +415,55 @@ impl App {
 
         // Update speedtest if running (don't auto-close, user must press C)
         if let Some(ref mut st) = self.speedtest {
-            if let Err(e) = st.update().await {
-                error!("Speedtest update error: {}", e);
+            match st.update().await {
+                Ok(true) => {
+                    if let Some(log) = &mut self.log {
+                        log.speedtest(st.get_state());
+                    }
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.speedtest(st.get_state());
+                    }
+                    if let Some(metrics) = &self.metrics {
+                        metrics.speedtest(st.get_state());
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => error!("Speedtest update error: {}", e),
             }
         }
 
+        if let Some(trace) = &mut self.traceroute {
+            trace.update();
+        }
+
         // Update portscan if running (don't auto-close, user must press C)
         if let Some(ref mut ps) = self.portscan {
+            let was_complete = ps.is_complete();
             if let Err(e) = ps.update().await {
                 error!("Portscan update error: {}", e);
             }
+            if !was_complete && ps.is_complete() {
+                if let Some(log) = &mut self.log {
+                    log.portscan(ps.target(), ps.target_ip(), ps.results());
+                }
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.portscan(ps.target(), ps.results());
+                }
+            }
+        }
+
+        if let Some(mut api) = self.api.take() {
+            for (request, reply) in api.pending() {
+                let _ = reply.send(api::respond(self, &request).await);
+            }
+            self.api = Some(api);
+        }
+
+        if let Some(log) = &mut self.log {
+            log.tick();
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.flush();
         }
 
         Ok(())
@@ -190,43 +496,227 @@ impl App {
     }
 
     pub fn reset_stats(&mut self) {
-        info!("Resetting statistics for {}", self.target);
-        self.ping_monitor.reset();
+        let mut resolved = Vec::new();
+        for session in &mut self.targets {
+            info!("Resetting statistics for {}", session.target);
+            session.ping_monitor.reset();
+            session.stats = session.ping_monitor.stats();
+            resolved.extend(session.alerts.clear());
+        }
+        for event in &resolved {
+            self.dispatch_alert(event);
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.reset();
+        }
         self.start_time = Instant::now();
     }
 
     pub async fn toggle_web_check(&mut self) {
         self.enable_web_check = !self.enable_web_check;
         info!("Toggling web check: {}", self.enable_web_check);
-        let _ = self
-            .ping_tx
-            .send(PingCommand::ToggleWebCheck(self.enable_web_check))
-            .await;
+        for session in &self.targets {
+            let _ = session
+                .ping_tx
+                .send(PingCommand::ToggleWebCheck(self.enable_web_check))
+                .await;
+        }
     }
 
     pub async fn start_speedtest(&mut self) -> Result<()> {
         if self.speedtest.is_none() {
-            info!("Starting speedtest for {}", self.target);
-            self.speedtest = Some(SpeedTest::new(&self.target).await?);
+            info!("Starting speedtest for {}", self.target());
+            self.speedtest = Some(SpeedTest::new(self.target()).await?);
         }
         Ok(())
     }
 
+    pub fn start_mtu_discovery(&mut self) {
+        let timeout = self.config.probe_timeout();
+        self.targets[self.selected_target].start_mtu_discovery(timeout);
+    }
+
     pub async fn start_portscan(&mut self) -> Result<()> {
         if self.portscan.is_none() {
-            info!("Starting port scan for {}", self.target);
-            self.portscan = Some(PortScanner::new(&self.target).await?);
+            info!("Starting port scan for {}", self.target());
+            self.portscan =
+                Some(PortScanner::new(self.target(), self.config.probe_timeout()).await?);
         }
         Ok(())
     }
 
+    /// Start resolving a new target in the background. It is monitored
+    /// alongside the existing ones once `tick` sees the task finish.
+    pub fn add_target(&mut self, target: String) -> Result<()> {
+        if self.targets.iter().any(|s| s.target == target) {
+            anyhow::bail!("{target} is already being monitored");
+        }
+        if let Some((pending, _)) = &self.adding_target {
+            anyhow::bail!("Still resolving {pending}");
+        }
+        info!("Adding target: {}", target);
+        let config = self.config.clone();
+        let task = tokio::spawn({
+            let target = target.clone();
+            async move { TargetSession::start(target, &config).await }
+        });
+        self.adding_target = Some((target, task));
+        Ok(())
+    }
+
+    /// The target being resolved, if any.
+    pub fn adding_target(&self) -> Option<&str> {
+        self.adding_target
+            .as_ref()
+            .map(|(target, _)| target.as_str())
+    }
+
+    /// Add the target started by `add_target` once its task has finished.
+    async fn poll_add_target(&mut self) {
+        if !self
+            .adding_target
+            .as_ref()
+            .is_some_and(|(_, task)| task.is_finished())
+        {
+            return;
+        }
+        let Some((target, task)) = self.adding_target.take() else {
+            return;
+        };
+        let result = match task.await {
+            Ok(result) => result,
+            Err(e) => Err(e.into()),
+        };
+        match result {
+            Ok(session) => {
+                self.push_target(session).await;
+                if self.show_add_target {
+                    self.close_add_target();
+                }
+            }
+            Err(e) => {
+                error!("Failed to add target {}: {}", target, e);
+                self.add_target_error = Some(format!("{target}: {e}"));
+            }
+        }
+    }
+
+    async fn push_target(&mut self, session: TargetSession) {
+        if self.enable_web_check {
+            let _ = session
+                .ping_tx
+                .send(PingCommand::ToggleWebCheck(true))
+                .await;
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.add_target(
+                &session.target,
+                session.ping_monitor.get_target_addr(),
+                session.ping_monitor.probe_method,
+            );
+        }
+        self.targets.push(session);
+        self.selected_target = self.targets.len() - 1;
+        self.restart_traceroute();
+    }
+
+    /// Stop monitoring the selected target. The last target cannot be removed.
+    pub fn remove_target(&mut self) {
+        if self.targets.len() <= 1 {
+            return;
+        }
+        let mut session = self.targets.remove(self.selected_target);
+        info!("Removing target: {}", session.target);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.remove_target(self.selected_target);
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.remove_target(&session.target);
+        }
+        let _ = session.ping_tx.try_send(PingCommand::Stop);
+        for event in session.alerts.clear() {
+            self.dispatch_alert(&event);
+        }
+        self.selected_target = self.selected_target.min(self.targets.len() - 1);
+        self.restart_traceroute();
+    }
+
+    pub fn open_add_target(&mut self) {
+        self.show_add_target = true;
+        self.add_target_input.clear();
+        self.add_target_error = None;
+    }
+
+    pub fn close_add_target(&mut self) {
+        self.show_add_target = false;
+        self.add_target_input.clear();
+        self.add_target_error = None;
+    }
+
+    /// Submit the add-target prompt. The prompt stays open while the target
+    /// resolves, and errors stay visible in it instead of aborting.
+    pub fn submit_add_target(&mut self) {
+        let target = self.add_target_input.trim().to_string();
+        if target.is_empty() {
+            return;
+        }
+        self.add_target_error = None;
+        if let Err(e) = self.add_target(target) {
+            error!("Failed to add target: {}", e);
+            self.add_target_error = Some(e.to_string());
+        }
+    }
+
+    pub fn next_target(&mut self) {
+        self.selected_target = (self.selected_target + 1) % self.targets.len();
+        debug!("Selected target: {}", self.target());
+        self.restart_traceroute();
+    }
+
+    pub fn prev_target(&mut self) {
+        self.selected_target = self
+            .selected_target
+            .checked_sub(1)
+            .unwrap_or(self.targets.len() - 1);
+        debug!("Selected target: {}", self.target());
+        self.restart_traceroute();
+    }
+
+    /// Open the traceroute view for the selected target, or close it again.
+    /// The trace only runs while the view is open.
+    pub fn toggle_traceroute(&mut self) {
+        if self.view == AppView::Trace {
+            self.traceroute = None;
+            self.switch_view(AppView::Detail);
+        } else {
+            self.switch_view(AppView::Trace);
+            self.restart_traceroute();
+        }
+    }
+
+    fn restart_traceroute(&mut self) {
+        if self.view != AppView::Trace {
+            return;
+        }
+        let addr = self.ping_monitor().get_target_addr();
+        info!("Starting traceroute to {} ({})", self.target(), addr);
+        self.traceroute = Some(Traceroute::start(
+            self.target(),
+            addr,
+            self.config.ping_interval_ms.max(500),
+            self.config.probe_timeout(),
+        ));
+    }
+
     pub fn increase_history(&mut self) {
         // Increase by 10 seconds
         let new_len = self.config.graph_history_length + 10;
         if new_len <= 600 {
             // Max 10 minutes
             self.config.graph_history_length = new_len;
-            self.ping_monitor.set_max_history(new_len);
+            for session in &mut self.targets {
+                session.ping_monitor.set_max_history(new_len);
+            }
         }
     }
 
@@ -236,7 +726,9 @@ impl App {
         if new_len >= 30 {
             // Min 30 seconds
             self.config.graph_history_length = new_len;
-            self.ping_monitor.set_max_history(new_len);
+            for session in &mut self.targets {
+                session.ping_monitor.set_max_history(new_len);
+            }
         }
     }
 
@@ -246,9 +738,7 @@ impl App {
         let new_interval = self.config.ping_interval_ms.saturating_sub(50);
         if new_interval >= 50 {
             self.config.ping_interval_ms = new_interval;
-            let _ = self
-                .ping_tx
-                .try_send(PingCommand::SetInterval(new_interval));
+            self.broadcast(PingCommand::SetInterval(new_interval));
         }
     }
 
@@ -258,15 +748,29 @@ impl App {
         let new_interval = self.config.ping_interval_ms + 50;
         if new_interval <= 5000 {
             self.config.ping_interval_ms = new_interval;
-            let _ = self
-                .ping_tx
-                .try_send(PingCommand::SetInterval(new_interval));
+            self.broadcast(PingCommand::SetInterval(new_interval));
+        }
+    }
+
+    /// Send a command to every target's ping task without blocking the UI loop.
+    fn broadcast(&self, cmd: PingCommand) {
+        for session in &self.targets {
+            let _ = session.ping_tx.try_send(cmd.clone());
         }
     }
 
-    // TODO: Implement view switching when multi-target is added
-    // pub fn switch_view(&mut self, _view: u8) {
-    // }
+    pub fn switch_view(&mut self, view: AppView) {
+        debug!("Switching view: {:?}", view);
+        self.view = view;
+    }
+
+    pub fn toggle_view(&mut self) {
+        self.traceroute = None;
+        self.switch_view(match self.view {
+            AppView::Detail | AppView::Trace => AppView::Overview,
+            AppView::Overview => AppView::Detail,
+        });
+    }
 
     pub fn settings_navigate_up(&mut self) {
         if self.show_settings && self.settings_selected > 0 {
@@ -289,11 +793,168 @@ impl App {
             0 => self.show_jitter = !self.show_jitter,
             1 => self.show_history = !self.show_history,
             2 => self.config.pause_ping_during_speedtest = !self.config.pause_ping_during_speedtest,
-            _ => {}
+            _ => self.settings_adjust_selected(1),
         }
     }
 
+    /// Step the selected value setting (timeout, payload size or pattern) up or down
+    /// and apply it to every running ping task.
+    pub fn settings_adjust_selected(&mut self, direction: i32) {
+        if !self.show_settings {
+            return;
+        }
+
+        match self.settings_selected {
+            3 => {
+                let step = 250 * direction as i64;
+                self.config.probe_timeout_ms =
+                    (self.config.probe_timeout_ms as i64 + step).clamp(250, 10_000) as u64;
+            }
+            4 => {
+                self.config.payload_size =
+                    step_preset(PAYLOAD_SIZES, &self.config.payload_size, direction);
+            }
+            5 => {
+                let current = self.config.payload_pattern.as_str();
+                self.config.payload_pattern =
+                    step_preset(PAYLOAD_PATTERNS, &current, direction).to_string();
+            }
+            _ => return,
+        }
+        self.broadcast(PingCommand::SetProbeOptions(self.config.probe_options()));
+    }
+
     pub fn runtime(&self) -> Duration {
-        self.start_time.elapsed()
+        match &self.replay {
+            Some(player) => player.position(),
+            None => self.start_time.elapsed(),
+        }
+    }
+
+    /// Current time, or the recorded time at the playback position in a replay.
+    pub fn now(&self) -> DateTime<Local> {
+        self.replay
+            .as_ref()
+            .map_or_else(Local::now, |player| player.time())
+    }
+
+    pub fn replay_toggle_pause(&mut self) {
+        if let Some(mut player) = self.replay.take() {
+            player.toggle_pause(self);
+            self.replay = Some(player);
+        }
+    }
+
+    /// Jump forward or back in a replay by `offset_secs`.
+    pub fn replay_seek(&mut self, offset_secs: i64) {
+        if let Some(mut player) = self.replay.take() {
+            player.seek(self, offset_secs);
+            self.replay = Some(player);
+        }
+    }
+
+    pub fn replay_seek_to(&mut self, position: Duration) {
+        if let Some(mut player) = self.replay.take() {
+            player.seek_to(self, position);
+            self.replay = Some(player);
+        }
+    }
+
+    pub fn replay_set_speed(&mut self, speed: u32) {
+        if let Some(player) = &mut self.replay {
+            debug!("Replay speed {}x", speed);
+            player.speed = speed;
+        }
+    }
+}
+
+/// Payload sizes offered in the settings overlay; 1472 fills a 1500 byte MTU.
+const PAYLOAD_SIZES: &[usize] = &[0, 8, 32, 56, 120, 512, 1000, 1400, 1472, 1500, 4000, 8972];
+const PAYLOAD_PATTERNS: &[&str] = &["00", "55", "a5", "aa", "deadbeef", "ff"];
+
+/// Move to the next/previous preset, starting from the nearest one when the
+/// current value was set on the command line and is not in the list.
+fn step_preset<T: Copy + PartialOrd>(presets: &[T], current: &T, direction: i32) -> T {
+    let index = if direction > 0 {
+        presets
+            .iter()
+            .position(|p| p > current)
+            .unwrap_or(presets.len() - 1)
+    } else {
+        presets.iter().rposition(|p| p < current).unwrap_or(0)
+    };
+    presets[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(targets: &[&str]) -> App {
+        let config = Config::default();
+        let sessions = targets
+            .iter()
+            .enumerate()
+            .map(|(i, target)| {
+                let addr = IpAddr::from([192, 0, 2, i as u8 + 1]);
+                TargetSession::replay(target.to_string(), addr, ProbeMethod::Icmp, &config)
+            })
+            .collect();
+        App::with_sessions(sessions, config, true)
+    }
+
+    #[test]
+    fn test_target_selection_wraps() {
+        let mut app = app(&["a", "b", "c"]);
+        app.prev_target();
+        assert_eq!(app.target(), "c");
+        app.next_target();
+        assert_eq!(app.target(), "a");
+        app.next_target();
+        assert_eq!(app.target(), "b");
+    }
+
+    #[test]
+    fn test_selection_stays_in_bounds_after_removal() {
+        let mut app = app(&["a", "b", "c"]);
+        app.prev_target();
+        app.remove_target();
+        assert_eq!(app.targets.len(), 2);
+        assert_eq!(app.selected_target, 1);
+        assert_eq!(app.target(), "b");
+
+        app.next_target();
+        assert_eq!(app.target(), "a");
+        app.remove_target();
+        assert_eq!(app.target(), "b");
+        // The last target stays
+        app.remove_target();
+        assert_eq!(app.targets.len(), 1);
+        assert_eq!(app.selected_target, 0);
+    }
+
+    #[tokio::test]
+    async fn test_add_target_resolves_in_background() {
+        let mut app = app(&["a"]);
+        assert!(app.add_target("a".to_string()).is_err());
+
+        app.add_target("127.0.0.1".to_string()).unwrap();
+        assert_eq!(app.adding_target(), Some("127.0.0.1"));
+        assert!(app.add_target("127.0.0.2".to_string()).is_err());
+        // Nothing is added until the task finishes
+        assert_eq!(app.targets.len(), 1);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while app.adding_target().is_some() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                app.poll_add_target().await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(app.add_target_error, None);
+        assert_eq!(app.targets.len(), 2);
+        assert_eq!(app.target(), "127.0.0.1");
+        assert!(app.add_target("127.0.0.1".to_string()).is_err());
     }
 }
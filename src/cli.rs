@@ -0,0 +1,572 @@
+use crate::headless::parse_duration;
+use crate::network::{parse_payload_pattern, ProbeMethod};
+use crate::probe_log::{parse_size, LogFormat, LogRotation, ProbeLog};
+use crate::sink::{InfluxEndpoint, Sink};
+use crate::storage::Config;
+use crate::webhook::WebhookFormat;
+use clap::{Args, Parser, Subcommand};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const KEYBINDINGS: &str = "\
+Controls (TUI):
+  q, Q          Quit
+  s, S          Start Speedtest
+  p, P          Start Port Scan
+  c, C          Close Speedtest / Port Scan panel
+  j, J          Toggle Jitter Panel
+  h, H          Toggle History Panel
+  r, R          Reset Statistics
+  w, W          Toggle Web Check (TCP 80/443)
+  m, M          Discover path MTU (shown in Diagnostics)
+  t, T          Traceroute / MTR view of the selected target
+  a, A          Add a target to this session
+  x, X          Remove the selected target
+  Tab/S-Tab     Switch between targets
+  v, V          Toggle overview of all targets
+  Enter         Toggle Diagnostics
+  Esc           Settings / close overlay
+  Up/Down       Ping faster / slower
+  Left/Right    Shorter / longer graph history";
+
+const REPLAY_KEYBINDINGS: &str = "\
+Controls (replay):
+  Space         Pause / resume (restarts at the end)
+  Left/Right    Back / forward 10 seconds
+  PgUp/PgDn     Back / forward 1 minute
+  Home/End      Jump to start / end
+  1, 2, 3       Play at 1x, 10x, 100x
+  j, h, v, Tab  Panels and views as in the monitor
+  q, Q          Quit";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "rping",
+    version,
+    about = "High-performance network monitoring for professionals.",
+    args_conflicts_with_subcommands = true,
+    after_help = KEYBINDINGS
+)]
+pub struct Cli {
+    /// Force monochrome mode
+    #[arg(short = 'm', long, global = true)]
+    pub monotone: bool,
+
+    /// Enable verbose logging (level: trace)
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// List recent targets (same as `rping history`)
+    #[arg(long, hide = true)]
+    pub list: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Monitor options when no subcommand is given
+    #[command(flatten)]
+    pub monitor: MonitorArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Monitor one or more targets (default when no subcommand is given)
+    #[command(after_help = KEYBINDINGS)]
+    Monitor(Box<MonitorArgs>),
+    /// Scan common TCP ports on a target and print the results
+    Scan {
+        /// IP address or hostname to scan
+        target: String,
+    },
+    /// Discover the path MTU to a target using Don't-Fragment pings
+    Mtu {
+        /// IP address or hostname to probe
+        target: String,
+    },
+    /// Run a download/upload speed test and print the results
+    Speedtest,
+    /// Play back a recorded session in the TUI
+    #[command(after_help = REPLAY_KEYBINDINGS)]
+    Replay {
+        /// Recording file, or its name in the sessions directory
+        file: PathBuf,
+    },
+    /// Show recently monitored targets
+    History {
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Show or change the saved configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Print the saved configuration as JSON
+    Show,
+    /// Save new values for the given settings
+    Set(ConfigOverrides),
+    /// Restore the default configuration
+    Reset,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct MonitorArgs {
+    /// One or more IP addresses or hostnames to monitor
+    pub targets: Vec<String>,
+
+    #[command(flatten)]
+    pub log: LogArgs,
+
+    #[command(flatten)]
+    pub export: ExportArgs,
+
+    #[command(flatten)]
+    pub headless: HeadlessArgs,
+
+    #[command(flatten)]
+    pub overrides: ConfigOverrides,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+#[command(next_help_heading = "Log Options")]
+pub struct LogArgs {
+    /// Log every probe, web check, port scan and speed test to FILE
+    #[arg(long = "log", value_name = "FILE")]
+    pub file: Option<PathBuf>,
+
+    /// Format of the --log file
+    #[arg(long = "log-format", value_enum, value_name = "FORMAT", default_value_t = LogFormat::Csv)]
+    pub format: LogFormat,
+
+    /// Start a new log file once it reaches SIZE, e.g. 500K, 10M, 1G
+    #[arg(long = "log-max-size", value_name = "SIZE", value_parser = parse_size_arg)]
+    pub max_size: Option<u64>,
+
+    /// Start a new log file every day at midnight
+    #[arg(long = "log-daily")]
+    pub daily: bool,
+
+    /// Rotated log files to keep, 0 keeps all of them
+    #[arg(long = "log-keep", value_name = "N", default_value_t = 7)]
+    pub keep: usize,
+
+    /// Compress rotated log files with gzip
+    #[arg(long = "log-gzip")]
+    pub gzip: bool,
+}
+
+impl LogArgs {
+    /// Open the log file, if one was asked for.
+    pub fn open(&self) -> anyhow::Result<Option<ProbeLog>> {
+        let rotation = LogRotation {
+            max_bytes: self.max_size,
+            daily: self.daily,
+            keep: self.keep,
+            gzip: self.gzip,
+        };
+        self.file
+            .as_ref()
+            .map(|path| ProbeLog::open(path, self.format, rotation))
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, Default, Args)]
+#[command(next_help_heading = "Export Options")]
+pub struct ExportArgs {
+    /// Serve Prometheus metrics at http://ADDR/metrics; a bare port listens on localhost
+    #[arg(long, value_name = "ADDR", value_parser = parse_listen_addr)]
+    pub metrics: Option<SocketAddr>,
+
+    /// Serve a JSON API at http://ADDR/api (TUI only); a bare port listens on localhost
+    #[arg(long, value_name = "ADDR", value_parser = parse_listen_addr)]
+    pub api: Option<SocketAddr>,
+
+    /// Push InfluxDB line protocol to udp://HOST:PORT or an HTTP write URL
+    #[arg(long, value_name = "URL")]
+    pub influx: Option<InfluxEndpoint>,
+
+    /// API token for --influx over HTTP
+    #[arg(long, value_name = "TOKEN", requires = "influx")]
+    pub influx_token: Option<String>,
+
+    /// Push StatsD timers, counters and gauges to HOST:PORT over UDP
+    #[arg(long, value_name = "HOST:PORT")]
+    pub statsd: Option<String>,
+
+    /// How often --influx and --statsd get aggregate statistics, e.g. 10s
+    #[arg(long, value_name = "TIME", default_value = "10s", value_parser = parse_duration_arg)]
+    pub push_interval: Duration,
+}
+
+impl ExportArgs {
+    /// Start the --influx and --statsd sinks that were asked for.
+    pub async fn sinks(&self) -> anyhow::Result<Vec<Sink>> {
+        let mut sinks = Vec::new();
+        if let Some(endpoint) = &self.influx {
+            sinks.push(
+                Sink::influx(
+                    endpoint.clone(),
+                    self.influx_token.clone(),
+                    self.push_interval,
+                )
+                .await?,
+            );
+        }
+        if let Some(addr) = &self.statsd {
+            sinks.push(Sink::statsd(addr, self.push_interval).await?);
+        }
+        Ok(sinks)
+    }
+}
+
+#[derive(Debug, Clone, Default, Args)]
+#[command(next_help_heading = "Headless Options")]
+pub struct HeadlessArgs {
+    /// Print classic ping output instead of the TUI
+    #[arg(long)]
+    pub no_tui: bool,
+
+    /// Stop after N probes per target (implies --no-tui)
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub count: Option<u64>,
+
+    /// Stop after TIME, e.g. 30s, 5m (implies --no-tui)
+    #[arg(long, value_name = "TIME", value_parser = parse_duration_arg)]
+    pub duration: Option<Duration>,
+
+    /// Exit with code 1 if packet loss exceeds PCT
+    #[arg(long, value_name = "PCT", value_parser = parse_percent)]
+    pub max_loss: Option<f64>,
+
+    /// Exit with code 1 if average latency exceeds MS
+    #[arg(long, value_name = "MS", value_parser = parse_positive_ms)]
+    pub max_avg: Option<f64>,
+}
+
+impl HeadlessArgs {
+    pub fn enabled(&self) -> bool {
+        self.no_tui || self.count.is_some() || self.duration.is_some()
+    }
+}
+
+/// Command-line overrides for every `Config` field.
+#[derive(Debug, Clone, Default, Args)]
+#[command(next_help_heading = "Config Overrides")]
+pub struct ConfigOverrides {
+    /// Ping interval in milliseconds
+    #[arg(short, long, value_name = "MS", value_parser = clap::value_parser!(u64).range(50..=5000))]
+    pub interval: Option<u64>,
+
+    /// Graph history length in samples
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(30..=600))]
+    pub history_length: Option<u64>,
+
+    /// Show the jitter panel
+    #[arg(long, value_name = "BOOL")]
+    pub jitter_panel: Option<bool>,
+
+    /// Show the history panel
+    #[arg(long, value_name = "BOOL")]
+    pub history_panel: Option<bool>,
+
+    /// Pause pinging while a speed test runs
+    #[arg(long, value_name = "BOOL")]
+    pub pause_during_speedtest: Option<bool>,
+
+    /// Speed test provider
+    #[arg(long, value_name = "NAME")]
+    pub speedtest_provider: Option<String>,
+
+    /// Probe with TCP handshakes to PORT instead of ICMP (no raw-socket privileges needed)
+    #[arg(long, value_name = "PORT", value_parser = clap::value_parser!(u16).range(1..), conflicts_with = "icmp")]
+    pub tcp: Option<u16>,
+
+    /// Probe with ICMP Echo (default)
+    #[arg(long)]
+    pub icmp: bool,
+
+    /// Probe timeout in milliseconds (also used by web checks and port scans)
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(100..=60000))]
+    pub timeout: Option<u64>,
+
+    /// ICMP payload size in bytes
+    #[arg(short = 's', long, value_name = "BYTES", value_parser = clap::value_parser!(u16).range(0..=65500))]
+    pub payload_size: Option<u16>,
+
+    /// ICMP payload pattern as hex bytes, e.g. ff or deadbeef
+    #[arg(short = 'p', long, value_name = "HEX", value_parser = parse_pattern_arg)]
+    pub payload_pattern: Option<String>,
+
+    /// Consecutive timeouts that count as an outage
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=1000))]
+    pub outage_threshold: Option<u32>,
+
+    /// Check alert rules (turned on by the alert flags below unless given)
+    #[arg(long, value_name = "BOOL")]
+    pub alerts: Option<bool>,
+
+    /// Ring the terminal bell when an alert fires
+    #[arg(long, value_name = "BOOL")]
+    pub alert_bell: Option<bool>,
+
+    /// Shell command run on every alert event (details in RPING_ALERT_* variables)
+    #[arg(long, value_name = "CMD")]
+    pub alert_command: Option<String>,
+
+    /// URL receiving alert events as JSON POSTs (empty to disable)
+    #[arg(long, value_name = "URL")]
+    pub webhook: Option<String>,
+
+    /// Payload layout for --webhook
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub webhook_format: Option<WebhookFormat>,
+
+    /// Record the session to a replayable file under the config directory
+    #[arg(long, value_name = "BOOL")]
+    pub record: Option<bool>,
+
+    /// Recordings kept before the oldest are deleted
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_recordings: Option<u64>,
+}
+
+impl ConfigOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.interval.is_none()
+            && self.history_length.is_none()
+            && self.jitter_panel.is_none()
+            && self.history_panel.is_none()
+            && self.pause_during_speedtest.is_none()
+            && self.speedtest_provider.is_none()
+            && self.tcp.is_none()
+            && !self.icmp
+            && self.timeout.is_none()
+            && self.payload_size.is_none()
+            && self.payload_pattern.is_none()
+            && self.outage_threshold.is_none()
+            && self.alerts.is_none()
+            && self.alert_bell.is_none()
+            && self.alert_command.is_none()
+            && self.webhook.is_none()
+            && self.webhook_format.is_none()
+            && self.record.is_none()
+            && self.max_recordings.is_none()
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(ms) = self.interval {
+            config.ping_interval_ms = ms;
+        }
+        if let Some(len) = self.history_length {
+            config.graph_history_length = len as usize;
+        }
+        if let Some(show) = self.jitter_panel {
+            config.show_jitter_panel = show;
+        }
+        if let Some(show) = self.history_panel {
+            config.show_history_panel = show;
+        }
+        if let Some(pause) = self.pause_during_speedtest {
+            config.pause_ping_during_speedtest = pause;
+        }
+        if let Some(provider) = &self.speedtest_provider {
+            config.speedtest_provider = provider.clone();
+        }
+        if let Some(port) = self.tcp {
+            config.probe_method = ProbeMethod::Tcp(port);
+        }
+        if self.icmp {
+            config.probe_method = ProbeMethod::Icmp;
+        }
+        if let Some(ms) = self.timeout {
+            config.probe_timeout_ms = ms;
+        }
+        if let Some(size) = self.payload_size {
+            config.payload_size = size as usize;
+        }
+        if let Some(pattern) = &self.payload_pattern {
+            config.payload_pattern = pattern.clone();
+        }
+        if let Some(n) = self.outage_threshold {
+            config.outage_threshold = n;
+        }
+        if let Some(bell) = self.alert_bell {
+            config.alerts.bell = bell;
+        }
+        if let Some(command) = &self.alert_command {
+            // An empty command removes a saved one
+            config.alerts.command = Some(command.clone()).filter(|c| !c.trim().is_empty());
+        }
+        if let Some(url) = &self.webhook {
+            if url.trim().is_empty() {
+                config.alerts.webhook = None;
+            } else {
+                config
+                    .alerts
+                    .webhook
+                    .get_or_insert_with(Default::default)
+                    .url = url.clone();
+            }
+        }
+        if let Some(format) = self.webhook_format {
+            if let Some(webhook) = &mut config.alerts.webhook {
+                webhook.format = format;
+            }
+        }
+        // Asking for an alert notification means wanting alerts
+        let notifies = self.alert_bell == Some(true)
+            || self
+                .alert_command
+                .as_ref()
+                .is_some_and(|c| !c.trim().is_empty())
+            || self.webhook.as_ref().is_some_and(|u| !u.trim().is_empty());
+        if let Some(enabled) = self.alerts {
+            config.alerts.enabled = enabled;
+        } else if notifies {
+            config.alerts.enabled = true;
+        }
+        if let Some(record) = self.record {
+            config.record_sessions = record;
+        }
+        if let Some(n) = self.max_recordings {
+            config.max_recordings = n as usize;
+        }
+    }
+}
+
+fn parse_duration_arg(s: &str) -> Result<Duration, String> {
+    parse_duration(s).map_err(|e| e.to_string())
+}
+
+fn parse_size_arg(s: &str) -> Result<u64, String> {
+    match parse_size(s) {
+        Ok(0) => Err("must be greater than 0".to_string()),
+        Ok(size) => Ok(size),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `9100` means `127.0.0.1:9100`; anything else must be a full `IP:PORT`.
+fn parse_listen_addr(s: &str) -> Result<SocketAddr, String> {
+    if let Ok(port) = s.parse::<u16>() {
+        return Ok(SocketAddr::from(([127, 0, 0, 1], port)));
+    }
+    s.parse()
+        .map_err(|_| format!("'{s}' is not a port or an IP:PORT address"))
+}
+
+fn parse_pattern_arg(s: &str) -> Result<String, String> {
+    parse_payload_pattern(s)
+        .map(|_| s.trim().trim_start_matches("0x").to_lowercase())
+        .map_err(|e| e.to_string())
+}
+
+fn parse_percent(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|_| format!("'{s}' is not a number"))?;
+    if (0.0..=100.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err("must be between 0 and 100".to_string())
+    }
+}
+
+fn parse_positive_ms(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|_| format!("'{s}' is not a number"))?;
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err("must be greater than 0".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_bare_targets_default_to_monitor() {
+        let cli = Cli::try_parse_from(["rping", "1.1.1.1", "8.8.8.8", "-c", "5"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.monitor.targets, vec!["1.1.1.1", "8.8.8.8"]);
+        assert_eq!(cli.monitor.headless.count, Some(5));
+        assert!(cli.monitor.headless.enabled());
+    }
+
+    #[test]
+    fn test_config_overrides_apply() {
+        let cli = Cli::try_parse_from([
+            "rping",
+            "monitor",
+            "--interval",
+            "200",
+            "--jitter-panel",
+            "false",
+            "--max-recordings",
+            "5",
+            "1.1.1.1",
+        ])
+        .unwrap();
+        let Some(Command::Monitor(args)) = cli.command else {
+            panic!("expected monitor subcommand");
+        };
+        let mut config = Config::default();
+        args.overrides.apply(&mut config);
+        assert_eq!(config.ping_interval_ms, 200);
+        assert!(!config.show_jitter_panel);
+        assert_eq!(config.max_recordings, 5);
+        assert_eq!(config.probe_method, ProbeMethod::Icmp);
+        assert_eq!(
+            config.graph_history_length,
+            Config::default().graph_history_length
+        );
+    }
+
+    #[test]
+    fn test_tcp_probe_override() {
+        let cli = Cli::try_parse_from(["rping", "--tcp", "443", "1.1.1.1"]).unwrap();
+        let mut config = Config::default();
+        cli.monitor.overrides.apply(&mut config);
+        assert_eq!(config.probe_method, ProbeMethod::Tcp(443));
+        assert!(Cli::try_parse_from(["rping", "--tcp", "443", "--icmp", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_alert_flags_enable_alerts() {
+        let apply = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["rping"].iter().chain(args).chain(&["x"])).unwrap();
+            let mut config = Config::default();
+            cli.monitor.overrides.apply(&mut config);
+            config.alerts
+        };
+        assert!(!apply(&[]).enabled);
+        assert!(apply(&["--alerts", "true"]).enabled);
+        assert!(apply(&["--alert-bell", "true"]).enabled);
+        assert!(apply(&["--webhook", "https://example.com/hook"]).enabled);
+        assert!(!apply(&["--webhook", ""]).enabled);
+        let alerts = apply(&["--alert-command", "true", "--alerts", "false"]);
+        assert!(!alerts.enabled && alerts.command.is_some());
+    }
+
+    #[test]
+    fn test_invalid_values_are_rejected() {
+        assert!(Cli::try_parse_from(["rping", "--interval", "10", "x"]).is_err());
+        assert!(Cli::try_parse_from(["rping", "--max-loss", "150", "x"]).is_err());
+        assert!(Cli::try_parse_from(["rping", "--duration", "5y", "x"]).is_err());
+        assert!(Cli::try_parse_from(["rping", "--bogus", "x"]).is_err());
+        assert!(Cli::try_parse_from(["rping", "--max-recordings", "0", "x"]).is_err());
+        assert!(Cli::try_parse_from(["rping", "--payload-pattern", "xyz", "x"]).is_err());
+    }
+}
@@ -0,0 +1,469 @@
+use crate::app::TargetSession;
+use crate::metrics::Metrics;
+use crate::network::{
+    discover_path_mtu, format_duration, NetworkStats, PathMtu, PingMonitor, PingResult,
+    PortScanner, PortStatus, ReplyKind, SpeedTest, SpeedTestState, UseCase,
+};
+use crate::probe_log::ProbeLog;
+use crate::recording::Recorder;
+use crate::sink::Sink;
+use crate::storage::Config;
+use anyhow::Result;
+use chrono::Local;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// Options for running without the TUI.
+#[derive(Debug, Clone, Default)]
+pub struct HeadlessOptions {
+    /// Stop after this many probes per target
+    pub count: Option<u64>,
+    /// Stop after this much wall-clock time
+    pub duration: Option<Duration>,
+    /// Fail if packet loss exceeds this percentage
+    pub max_loss_pct: Option<f64>,
+    /// Fail if average latency exceeds this many milliseconds
+    pub max_avg_ms: Option<f64>,
+}
+
+/// Parse durations like `30s`, `5m`, `1h`, `250ms`. A bare number means seconds.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+    let value: f64 = value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid duration: {input}"))?;
+    let secs = match unit {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => anyhow::bail!("Invalid duration unit in {input} (use ms, s, m or h)"),
+    };
+    Duration::try_from_secs_f64(secs).map_err(|_| anyhow::anyhow!("Duration {input} is too long"))
+}
+
+/// Run the ping loop without ratatui, printing classic ping-style output.
+/// Returns the process exit code: 0 when all thresholds are met, 1 otherwise.
+pub async fn run(
+    targets: Vec<String>,
+    config: Config,
+    opts: HeadlessOptions,
+    mut log: Option<ProbeLog>,
+    metrics: Option<Metrics>,
+    mut sinks: Vec<Sink>,
+) -> Result<i32> {
+    if targets.is_empty() {
+        anyhow::bail!("Headless mode requires at least one target");
+    }
+    info!("Starting headless session: {:?}", opts);
+
+    let multi = targets.len() > 1;
+    let mut sessions = Vec::with_capacity(targets.len());
+    for target in targets {
+        let session = TargetSession::start(target, &config).await?;
+        println!(
+            "PING {} ({}) via {} every {}ms",
+            session.target,
+            session.ping_monitor.get_target_addr(),
+            session.ping_monitor.probe_method,
+            config.ping_interval_ms
+        );
+        sessions.push(session);
+    }
+
+    if let Some(log) = &log {
+        println!("{}", log.describe());
+    }
+    if let Some(metrics) = &metrics {
+        println!("{}", metrics.describe());
+    }
+    for sink in &sinks {
+        println!("{}", sink.describe());
+    }
+
+    let mut recorder = if config.record_sessions {
+        let mut recorder = Recorder::create(&config)?;
+        for session in &sessions {
+            recorder.add_target(
+                &session.target,
+                session.ping_monitor.get_target_addr(),
+                session.ping_monitor.probe_method,
+            );
+        }
+        println!("Recording to {}", recorder.path().display());
+        Some(recorder)
+    } else {
+        None
+    };
+
+    let start = Instant::now();
+    let mut seqs = vec![0u64; sessions.len()];
+    let mut tick = tokio::time::interval(Duration::from_millis(50));
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            _ = tick.tick() => {}
+            _ = &mut ctrl_c => {
+                debug!("Interrupted, printing summary");
+                break;
+            }
+        }
+
+        for (i, session) in sessions.iter_mut().enumerate() {
+            while let Ok(result) = session.ping_rx.try_recv() {
+                if opts.count.is_some_and(|count| seqs[i] >= count) {
+                    break;
+                }
+                let prefix = if multi {
+                    format!("[{}] ", session.target)
+                } else {
+                    String::new()
+                };
+                match &result {
+                    PingResult::Success(reply) => {
+                        let note = match reply.kind {
+                            ReplyKind::OnTime => "",
+                            ReplyKind::Reordered => " (out of order)",
+                            ReplyKind::Late => " (late)",
+                            ReplyKind::Duplicate => " (DUP!)",
+                        };
+                        // Late and duplicate replies don't count as a new probe
+                        if matches!(reply.kind, ReplyKind::OnTime | ReplyKind::Reordered) {
+                            seqs[i] += 1;
+                        }
+                        let ttl = reply.ttl.map(|t| format!(" ttl={t}")).unwrap_or_default();
+                        let bytes = if reply.size > 0 {
+                            format!("{} bytes ", reply.size)
+                        } else {
+                            String::new()
+                        };
+                        println!(
+                            "{prefix}{bytes}from {}: seq={}{ttl} time={:.2} ms{note}",
+                            reply.source, reply.seq, reply.rtt_ms
+                        );
+                    }
+                    PingResult::Timeout { seq, .. } => {
+                        seqs[i] += 1;
+                        println!("{prefix}Request timeout for seq={seq}");
+                    }
+                    PingResult::WebCheck { .. } => {}
+                }
+                if let Some(log) = &mut log {
+                    log.ping(
+                        &session.target,
+                        session.ping_monitor.get_target_addr(),
+                        session.ping_monitor.probe_method,
+                        &result,
+                    );
+                }
+                if let Some(recorder) = &mut recorder {
+                    recorder.ping(i, &result);
+                }
+                for sink in &mut sinks {
+                    sink.ping(
+                        &session.target,
+                        session.ping_monitor.get_target_addr(),
+                        session.ping_monitor.probe_method,
+                        &result,
+                    );
+                }
+                if let Some(metrics) = &metrics {
+                    metrics.ping(
+                        &session.target,
+                        session.ping_monitor.get_target_addr(),
+                        session.ping_monitor.probe_method,
+                        &result,
+                    );
+                }
+                session.ping_monitor.process_result(result);
+            }
+            if let Some(metrics) = &metrics {
+                metrics.stats(&session.target, &session.ping_monitor.stats());
+            }
+        }
+        for sink in &mut sinks {
+            sink.stats(
+                sessions
+                    .iter()
+                    .map(|s| (s.target.as_str(), s.ping_monitor.stats())),
+            );
+        }
+        if let Some(log) = &mut log {
+            log.tick();
+        }
+        if let Some(recorder) = &mut recorder {
+            recorder.flush();
+        }
+
+        let count_done = opts
+            .count
+            .is_some_and(|count| seqs.iter().all(|&s| s >= count));
+        let duration_done = opts.duration.is_some_and(|d| start.elapsed() >= d);
+        if count_done || duration_done {
+            break;
+        }
+    }
+
+    for mut sink in sinks {
+        // Final aggregates, so short runs report them too
+        sink.stats_now(
+            sessions
+                .iter()
+                .map(|s| (s.target.as_str(), s.ping_monitor.stats())),
+        );
+        sink.close().await;
+    }
+
+    let mut exit_code = 0;
+    for session in &sessions {
+        let stats = session.ping_monitor.stats();
+        print_summary(&session.target, &session.ping_monitor, &stats);
+        print_outages(&session.ping_monitor, &stats);
+        let failures = threshold_failures(&stats, &opts);
+        for failure in &failures {
+            println!("FAIL: {failure}");
+        }
+        if !failures.is_empty() {
+            exit_code = 1;
+        }
+    }
+
+    Ok(exit_code)
+}
+
+/// Scan the common port list and print one line per open or filtered port.
+pub async fn run_scan(target: &str, timeout: Duration) -> Result<i32> {
+    let mut scanner = PortScanner::new(target, timeout).await?;
+    println!("Scanning {target}...");
+    while !scanner.update().await? {}
+
+    let results = scanner.results();
+    for result in results.iter().filter(|r| r.status != PortStatus::Closed) {
+        let state = if result.status == PortStatus::Open {
+            "open"
+        } else {
+            "filtered"
+        };
+        println!(
+            "{:>5}/tcp  {:<9} {}",
+            result.port,
+            state,
+            result.service.as_deref().unwrap_or("")
+        );
+    }
+    let open = results
+        .iter()
+        .filter(|r| r.status == PortStatus::Open)
+        .count();
+    println!();
+    println!("{} ports scanned, {} open", results.len(), open);
+    Ok(0)
+}
+
+/// Discover and print the path MTU to `target`.
+pub async fn run_mtu(target: &str, timeout: Duration) -> Result<i32> {
+    let addr: IpAddr = match target.parse() {
+        Ok(addr) => addr,
+        Err(_) => tokio::net::lookup_host(format!("{target}:0"))
+            .await?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Could not resolve hostname"))?
+            .ip(),
+    };
+    println!("Discovering path MTU to {target} ({addr})...");
+    match discover_path_mtu(addr, timeout).await {
+        Ok(PathMtu::Found { mtu, payload }) => {
+            println!("Path MTU: {mtu} bytes ({payload} byte ICMP payload)");
+            Ok(0)
+        }
+        Ok(_) => Ok(1),
+        Err(e) => {
+            eprintln!("Path MTU discovery failed: {e}");
+            Ok(1)
+        }
+    }
+}
+
+/// Run the download/upload test, printing each phase as it starts.
+pub async fn run_speedtest() -> Result<i32> {
+    let mut st = SpeedTest::new("speed.cloudflare.com").await?;
+    let mut phase = "";
+    loop {
+        let done = st.update().await?;
+        let current = match st.get_state() {
+            SpeedTestState::Preparing => "",
+            SpeedTestState::Downloading { .. } => "Testing download...",
+            SpeedTestState::Uploading { .. } => "Testing upload...",
+            SpeedTestState::Complete { .. } | SpeedTestState::Error(_) => "",
+        };
+        if current != phase && !current.is_empty() {
+            println!("{current}");
+            phase = current;
+        }
+        if done {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    match st.get_state() {
+        SpeedTestState::Complete {
+            download_mbps,
+            upload_mbps,
+            peak_speed,
+            ..
+        } => {
+            println!("Download: {download_mbps:.2} Mbps (peak {peak_speed:.2} Mbps)");
+            println!("Upload:   {upload_mbps:.2} Mbps");
+            Ok(0)
+        }
+        SpeedTestState::Error(e) => {
+            eprintln!("Speed test failed: {e}");
+            Ok(1)
+        }
+        _ => Ok(1),
+    }
+}
+
+fn print_summary(target: &str, monitor: &PingMonitor, stats: &NetworkStats) {
+    let total = &stats.lifetime;
+    println!();
+    println!("--- {target} ping statistics ---");
+    println!(
+        "{} packets transmitted, {} received, {:.1}% packet loss",
+        total.sent, total.received, total.loss_pct
+    );
+    if total.received > 0 {
+        println!(
+            "rtt min/avg/max/mdev = {:.2}/{:.2}/{:.2}/{:.2} ms",
+            total.min_ms, total.avg_ms, total.max_ms, total.std_dev
+        );
+        println!(
+            "jitter (RFC 3550) = {:.2} ms, mean consecutive difference = {:.2} ms",
+            stats.jitter,
+            monitor.lifetime_mean_delta()
+        );
+        let p = monitor.lifetime_percentiles();
+        println!(
+            "rtt p50/p90/p95/p99 = {:.2}/{:.2}/{:.2}/{:.2} ms",
+            p.p50, p.p90, p.p95, p.p99
+        );
+        let verdicts: Vec<String> = UseCase::ALL
+            .iter()
+            .map(|&use_case| format!("{use_case} {}", stats.verdict(use_case)))
+            .collect();
+        println!(
+            "quality R = {:.1}, MOS = {:.2} ({})",
+            stats.r_factor,
+            stats.mos,
+            verdicts.join(", ")
+        );
+    }
+}
+
+fn print_outages(monitor: &PingMonitor, stats: &NetworkStats) {
+    let summary = &stats.outages;
+    if summary.count == 0 {
+        return;
+    }
+    let mttr = summary
+        .mttr
+        .map(|d| format!(", MTTR {}", format_duration(d)))
+        .unwrap_or_default();
+    println!(
+        "{} outage(s) of {}+ lost probes, longest {}{mttr}",
+        summary.count,
+        monitor.outages.threshold,
+        format_duration(summary.longest)
+    );
+    let now = Local::now();
+    for outage in monitor.outage_list() {
+        let end = outage.end.map_or_else(
+            || "ongoing".to_string(),
+            |end| end.format("%Y-%m-%d %H:%M:%S").to_string(),
+        );
+        println!(
+            "  outage {} - {} ({}, {} lost)",
+            outage.start.format("%Y-%m-%d %H:%M:%S"),
+            end,
+            format_duration(outage.duration(now)),
+            outage.missed
+        );
+    }
+}
+
+/// Describe every user-given threshold the session violated.
+fn threshold_failures(stats: &NetworkStats, opts: &HeadlessOptions) -> Vec<String> {
+    let mut failures = Vec::new();
+    let has_thresholds = opts.max_loss_pct.is_some() || opts.max_avg_ms.is_some();
+    let total = &stats.lifetime;
+    if has_thresholds && total.sent == 0 {
+        failures.push("no probes completed".to_string());
+        return failures;
+    }
+    if let Some(max) = opts.max_loss_pct {
+        if total.loss_pct > max {
+            failures.push(format!(
+                "packet loss {:.1}% exceeds {max:.1}%",
+                total.loss_pct
+            ));
+        }
+    }
+    if let Some(max) = opts.max_avg_ms {
+        if total.received == 0 {
+            failures.push("no replies received".to_string());
+        } else if total.avg_ms > max {
+            failures.push(format!(
+                "average latency {:.2} ms exceeds {max:.2} ms",
+                total.avg_ms
+            ));
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::SpanStats;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(10));
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("99999999999999999999h").is_err());
+    }
+
+    #[test]
+    fn test_threshold_failures() {
+        let stats = NetworkStats {
+            lifetime: SpanStats {
+                sent: 10,
+                received: 8,
+                loss_pct: 20.0,
+                avg_ms: 80.0,
+                ..SpanStats::default()
+            },
+            ..NetworkStats::default()
+        };
+        let opts = HeadlessOptions {
+            max_loss_pct: Some(5.0),
+            max_avg_ms: Some(100.0),
+            ..HeadlessOptions::default()
+        };
+        let failures = threshold_failures(&stats, &opts);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].contains("packet loss"));
+    }
+}
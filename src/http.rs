@@ -0,0 +1,261 @@
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+/// Largest request accepted, headers and body together.
+const MAX_REQUEST: usize = 64 * 1024;
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An HTTP request as the local endpoints see it.
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    /// Path without the query string
+    pub path: String,
+    pub query: String,
+    /// Header names in lower case
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Value of a query parameter, percent-decoded.
+    pub fn param(&self, name: &str) -> Option<String> {
+        self.query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(n, _)| percent_decode(n) == name)
+            .map(|(_, v)| percent_decode(v))
+    }
+}
+
+/// Decode `%XX` escapes and `+` as in a form-encoded query string. Malformed
+/// escapes are kept as they are.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                if let Some(byte) = hex {
+                    out.push(byte);
+                    i += 3;
+                    continue;
+                }
+                out.push(b'%');
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        Self::new(status, "text/plain; charset=utf-8", body)
+    }
+
+    pub fn not_found() -> Self {
+        Self::text(404, "Not Found\n")
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+/// Bind `addr` and answer every request with `handler`, one request per
+/// connection, until the runtime shuts down.
+pub async fn serve<H, F>(addr: SocketAddr, name: &'static str, handler: H) -> Result<SocketAddr>
+where
+    H: Fn(Request) -> F + Send + Sync + 'static,
+    F: Future<Output = Response> + Send,
+{
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Cannot listen on {addr} for the {name} endpoint"))?;
+    let local = listener.local_addr()?;
+    info!("Serving {} on http://{}", name, local);
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("{} endpoint accept failed: {}", name, e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let handler = Arc::clone(&handler);
+            tokio::spawn(async move {
+                if let Err(e) = handle(stream, handler.as_ref()).await {
+                    debug!("{} request from {} failed: {}", name, peer, e);
+                }
+            });
+        }
+    });
+    Ok(local)
+}
+
+async fn handle<H, F>(mut stream: TcpStream, handler: &H) -> Result<()>
+where
+    H: Fn(Request) -> F,
+    F: Future<Output = Response>,
+{
+    let (response, head_only) =
+        match tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+            Ok(Ok(Some(request))) => {
+                debug!("{} {}", request.method, request.path);
+                let head_only = request.method == "HEAD";
+                (handler(request).await, head_only)
+            }
+            Ok(Ok(None)) => (Response::text(413, "Request too large\n"), false),
+            Ok(Err(e)) => (Response::text(400, format!("{e}\n")), false),
+            Err(_) => anyhow::bail!("timed out reading the request"),
+        };
+    // HEAD reports the length of the body a GET would get, without sending it
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    if !head_only {
+        stream.write_all(response.body.as_bytes()).await?;
+    }
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Read one request. `None` if it exceeds `MAX_REQUEST`.
+async fn read_request(stream: &mut TcpStream) -> Result<Option<Request>> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            anyhow::bail!("connection closed mid-request");
+        }
+        data.extend_from_slice(&buf[..n]);
+        if data.len() > MAX_REQUEST {
+            return Ok(None);
+        }
+        let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let head = String::from_utf8_lossy(&data[..end]).into_owned();
+        let mut lines = head.lines();
+        let mut parts = lines.next().unwrap_or_default().split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            anyhow::bail!("malformed request line");
+        };
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|l| l.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        let length = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .map(|(_, value)| value.parse::<usize>())
+            .transpose()?
+            .unwrap_or(0);
+        if end + 4 + length > MAX_REQUEST {
+            return Ok(None);
+        }
+        if data.len() < end + 4 + length {
+            continue;
+        }
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        return Ok(Some(Request {
+            method: method.to_ascii_uppercase(),
+            path: path.to_string(),
+            query: query.to_string(),
+            headers,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_are_percent_decoded() {
+        let request = Request {
+            method: "GET".to_string(),
+            path: "/api/stats".to_string(),
+            query: "target=2001%3Adb8%3A%3A1&na%6De=a+b&bad=100%&odd=%zz".to_string(),
+            headers: Vec::new(),
+        };
+        assert_eq!(request.param("target").as_deref(), Some("2001:db8::1"));
+        assert_eq!(request.param("name").as_deref(), Some("a b"));
+        assert_eq!(request.param("bad").as_deref(), Some("100%"));
+        assert_eq!(request.param("odd").as_deref(), Some("%zz"));
+        assert_eq!(request.param("missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_head_reports_body_length() {
+        let addr = serve("127.0.0.1:0".parse().unwrap(), "test", |_| async {
+            Response::text(200, "hello\n")
+        })
+        .await
+        .unwrap();
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"HEAD / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.contains("Content-Length: 6\r\n"), "{response}");
+        assert!(response.ends_with("\r\n\r\n"), "{response}");
+    }
+}
@@ -1,50 +1,39 @@
+mod alerts;
+mod api;
 mod app;
+mod cli;
+mod headless;
+mod http;
 mod menu;
+mod metrics;
 mod network;
+mod probe_log;
+mod recording;
+mod replay;
+mod sink;
 mod storage;
 #[cfg(test)]
 mod tests;
 mod theme;
 mod ui;
+mod webhook;
 
 use anyhow::Result;
 use app::App;
+use clap::Parser;
+use cli::{Cli, Command, ConfigAction, MonitorArgs};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use menu::MenuApp;
+use network::ProbeMethod;
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io::{self};
 use tracing::{debug, info, trace};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
-fn print_help() {
-    println!("RustyPing v2.7.0");
-    println!("High-performance network monitoring for professionals.");
-    println!("Usage: rping [OPTIONS] [TARGET]");
-    println!();
-    println!("Arguments:");
-    println!("  [TARGET]      IP address or hostname to monitor");
-    println!();
-    println!("Options:");
-    println!("  -h, --help    Print this help message");
-    println!("  --list        List recent targets");
-    println!("  -m, --monotone Force monochrome mode");
-    println!("  --log <FILE>  Log statistics to a CSV file");
-    println!("  -v, --verbose Enable verbose logging (level: trace)");
-    println!();
-    println!("Controls:");
-    println!("  q, Q          Quit");
-    println!("  s, S          Start Speedtest");
-    println!("  p, P          Start Port Scan");
-    println!("  j, J          Toggle Jitter Panel");
-    println!("  h, H          Toggle History Panel");
-    println!("  r, R          Reset Statistics");
-    println!("  Arrows        Adjust graph scale / history");
-}
-
 #[cfg(windows)]
 fn check_permissions() {
     use std::process::Command;
@@ -70,54 +59,24 @@ fn check_permissions() {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    check_permissions();
     // Parse CLI arguments early to determine log level
-    let mut target_arg = None;
-    let mut monotone = false;
-    let mut log_file = None;
-    let mut verbose = false;
-    let args_vec: Vec<String> = std::env::args().skip(1).collect();
-    let mut i = 0;
-
-    while i < args_vec.len() {
-        let arg = &args_vec[i];
-        match arg.as_str() {
-            "--help" | "-h" => {
-                print_help();
-                return Ok(());
-            }
-            "--list" => {
-                let history = storage::TargetHistory::load()?;
-                history.print_recent();
-                return Ok(());
-            }
-            "--monotone" | "-m" => {
-                monotone = true;
-            }
-            "--verbose" | "-v" => {
-                verbose = true;
-            }
-            "--log" => {
-                if i + 1 < args_vec.len() {
-                    log_file = Some(args_vec[i + 1].clone());
-                    i += 1;
-                } else {
-                    eprintln!("Error: --log requires a file path");
-                    return Ok(());
-                }
-            }
-            _ => {
-                if !arg.starts_with('-') {
-                    target_arg = Some(arg.clone());
-                }
-            }
-        }
-        i += 1;
-    }
+    let cli = Cli::parse();
+    let monotone = cli.monotone;
+    let verbose = cli.verbose;
+
+    // Only the TUI monitor gets debug-level logging; every other mode is scripted output
+    let headless = match &cli.command {
+        Some(Command::Monitor(args)) => args.headless.enabled(),
+        None if !cli.list => cli.monitor.headless.enabled(),
+        _ => true,
+    };
 
     // Initialize tracing for comprehensive debugging
     let log_level = if verbose {
         tracing::Level::TRACE
+    } else if headless {
+        // Keep stderr quiet so scripts only see command output and real problems
+        tracing::Level::WARN
     } else {
         tracing::Level::DEBUG
     };
@@ -130,6 +89,99 @@ async fn main() -> Result<()> {
     info!("Starting RustyPing v2.7.0 Deep Debug Session");
     debug!("Verbose mode: {}, Monotone: {}", verbose, monotone);
 
+    match cli.command {
+        Some(Command::Scan { target }) => {
+            let timeout = storage::TargetHistory::load()?.config.probe_timeout();
+            let code = headless::run_scan(&target, timeout).await?;
+            std::process::exit(code);
+        }
+        Some(Command::Mtu { target }) => {
+            let timeout = storage::TargetHistory::load()?.config.probe_timeout();
+            let code = headless::run_mtu(&target, timeout).await?;
+            std::process::exit(code);
+        }
+        Some(Command::Speedtest) => {
+            let code = headless::run_speedtest().await?;
+            std::process::exit(code);
+        }
+        Some(Command::Replay { file }) => run_replay(&file, monotone).await,
+        Some(Command::History { limit }) => {
+            storage::TargetHistory::load()?.print_recent(limit);
+            Ok(())
+        }
+        Some(Command::Config { action }) => run_config(action),
+        Some(Command::Monitor(args)) => run_monitor(*args, monotone).await,
+        None if cli.list => {
+            storage::TargetHistory::load()?.print_recent(10);
+            Ok(())
+        }
+        None => run_monitor(cli.monitor, monotone).await,
+    }
+}
+
+fn run_config(action: ConfigAction) -> Result<()> {
+    let mut history = storage::TargetHistory::load()?;
+    match action {
+        ConfigAction::Show => {}
+        ConfigAction::Set(overrides) => {
+            overrides.apply(&mut history.config);
+            history.save()?;
+        }
+        ConfigAction::Reset => {
+            history.config = storage::Config::default();
+            history.save()?;
+        }
+    }
+    println!("{}", serde_json::to_string_pretty(&history.config)?);
+    Ok(())
+}
+
+async fn run_monitor(args: MonitorArgs, monotone: bool) -> Result<()> {
+    let mut config = storage::TargetHistory::load()?.config;
+    args.overrides.apply(&mut config);
+    let log = args.log.open()?;
+    let metrics = match args.export.metrics {
+        Some(addr) => Some(metrics::Metrics::start(addr).await?),
+        None => None,
+    };
+    let sinks = args.export.sinks().await?;
+    let api = match args.export.api {
+        Some(addr) if !args.headless.enabled() => Some(api::ApiServer::start(addr).await?),
+        _ => None,
+    };
+
+    if args.headless.enabled() {
+        if args.export.api.is_some() {
+            anyhow::bail!("--api needs the TUI; use --metrics to read results in headless mode");
+        }
+        let opts = headless::HeadlessOptions {
+            count: args.headless.count,
+            duration: args.headless.duration,
+            max_loss_pct: args.headless.max_loss,
+            max_avg_ms: args.headless.max_avg,
+        };
+        let code = headless::run(args.targets, config, opts, log, metrics, sinks).await?;
+        info!("RustyPing v2.7.0 Headless Session Ended");
+        std::process::exit(code);
+    }
+
+    if config.probe_method == ProbeMethod::Icmp {
+        check_permissions();
+    }
+    // Printed before the alternate screen, so it is still there on exit
+    if let Some(log) = &log {
+        eprintln!("{}", log.describe());
+    }
+    if let Some(metrics) = &metrics {
+        eprintln!("{}", metrics.describe());
+    }
+    for sink in &sinks {
+        eprintln!("{}", sink.describe());
+    }
+    if let Some(api) = &api {
+        eprintln!("{}", api.describe());
+    }
+
     // Set theme mode
     crate::theme::Theme::set_monotone(monotone);
 
@@ -140,44 +192,65 @@ async fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let target = match target_arg {
-        Some(t) => Some(t),
-        None => {
-            let history = storage::TargetHistory::load()?;
-            let theme = if monotone {
-                crate::theme::Theme::monotone()
-            } else {
-                crate::theme::Theme::blacksite()
-            };
-            let menu = MenuApp::new(&history, theme);
-            // Run the menu app
-            menu.run(&mut terminal)?
-        }
+    let targets = if args.targets.is_empty() {
+        let history = storage::TargetHistory::load()?;
+        let theme = if monotone {
+            crate::theme::Theme::monotone()
+        } else {
+            crate::theme::Theme::blacksite()
+        };
+        let menu = MenuApp::new(&history, theme);
+        // Run the menu app
+        menu.run(&mut terminal)?
+            .filter(|t| !t.is_empty())
+            .into_iter()
+            .collect()
+    } else {
+        args.targets
     };
 
-    if let Some(target_str) = target {
-        if !target_str.is_empty() {
-            // Save target to history
-            let mut history = storage::TargetHistory::load()?;
-            history.add_target(&target_str);
-            history.save()?;
+    if !targets.is_empty() {
+        // Save targets to history
+        let mut history = storage::TargetHistory::load()?;
+        for target in &targets {
+            history.add_target(target);
+        }
+        history.save()?;
 
-            // Create app
-            let mut app = App::new(target_str, log_file, monotone).await?;
+        // Create app
+        let mut app = App::new(targets.clone(), config, log, metrics, sinks, api, monotone).await?;
 
-            // Run app
-            run_app(&mut terminal, &mut app).await?;
+        // Run app
+        run_app(&mut terminal, &mut app).await?;
+        for sink in app.sinks.drain(..) {
+            sink.close().await;
+        }
 
-            // Save final stats and config
-            let stats = app.ping_monitor.stats();
-            let mut history = storage::TargetHistory::load()?;
+        // Save final stats and config
+        let mut history = storage::TargetHistory::load()?;
 
-            // Update config with any changes made during session
-            history.config = app.config;
+        // Update config with any changes made during session.
+        // Command-line overrides only apply to this session, so they are not persisted.
+        if args.overrides.is_empty() {
+            history.config = app.config.clone();
+        } else {
+            debug!("Config overrides given on the command line, not saving session config");
+        }
 
-            history.update_stats(&app.target, stats.avg_response, stats.uptime_pct);
-            history.save()?;
+        for session in &app.targets {
+            // Targets added from the UI are recorded in history as well
+            if !targets.contains(&session.target) {
+                history.add_target(&session.target);
+            }
+            let stats = session.ping_monitor.stats();
+            history.update_stats(
+                &session.target,
+                stats.lifetime.avg_ms,
+                stats.lifetime.uptime_pct(),
+                session.ping_monitor.lifetime_percentiles(),
+            );
         }
+        history.save()?;
     }
 
     info!("RustyPing v2.7.0 Debug Session Ended");
@@ -193,6 +266,80 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+async fn run_replay(file: &std::path::Path, monotone: bool) -> Result<()> {
+    let player = replay::Player::open(file)?;
+    let config = storage::TargetHistory::load()?.config;
+    crate::theme::Theme::set_monotone(monotone);
+    let mut app = App::replay(player, &config, monotone);
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let result = run_replay_app(&mut terminal, &mut app).await;
+
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+    result
+}
+
+async fn run_replay_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+) -> Result<()> {
+    debug!("Entering replay loop");
+    loop {
+        terminal.draw(|f| ui::draw(f, app))?;
+
+        if crossterm::event::poll(std::time::Duration::from_millis(50_u64))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                trace!("Replay key pressed: {:?}", key.code);
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(()),
+                    KeyCode::Char(' ') => app.replay_toggle_pause(),
+                    KeyCode::Left => app.replay_seek(-10),
+                    KeyCode::Right => app.replay_seek(10),
+                    KeyCode::PageUp => app.replay_seek(-60),
+                    KeyCode::PageDown => app.replay_seek(60),
+                    KeyCode::Home => app.replay_seek_to(std::time::Duration::ZERO),
+                    KeyCode::End => app.replay_seek_to(std::time::Duration::MAX),
+                    KeyCode::Char(c @ '1'..='3') => {
+                        let index = c as usize - '1' as usize;
+                        app.replay_set_speed(replay::SPEEDS[index]);
+                    }
+                    KeyCode::Char('c') | KeyCode::Char('C') => {
+                        if app.speedtest.is_some() {
+                            app.speedtest = None;
+                        } else if app.portscan.is_some() {
+                            app.portscan = None;
+                        }
+                    }
+                    KeyCode::Char('j') | KeyCode::Char('J') => app.toggle_jitter_panel(),
+                    KeyCode::Char('h') | KeyCode::Char('H') => app.toggle_history_panel(),
+                    KeyCode::Char('v') | KeyCode::Char('V') => app.toggle_view(),
+                    KeyCode::Tab => app.next_target(),
+                    KeyCode::BackTab => app.prev_target(),
+                    KeyCode::Enter => app.toggle_diagnostics(),
+                    KeyCode::Esc if app.show_diagnostics => app.toggle_diagnostics(),
+                    _ => {}
+                }
+            }
+        }
+
+        app.tick().await?;
+    }
+}
+
 async fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
@@ -205,7 +352,18 @@ async fn run_app(
         // Handle events
         if crossterm::event::poll(std::time::Duration::from_millis(50_u64))? {
             if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
+                if key.kind == KeyEventKind::Press && app.show_add_target {
+                    // Add-target prompt captures all input while open
+                    match key.code {
+                        KeyCode::Esc => app.close_add_target(),
+                        KeyCode::Enter => app.submit_add_target(),
+                        KeyCode::Backspace => {
+                            app.add_target_input.pop();
+                        }
+                        KeyCode::Char(c) => app.add_target_input.push(c),
+                        _ => {}
+                    }
+                } else if key.kind == KeyEventKind::Press {
                     trace!("Key pressed: {:?}", key.code);
                     match key.code {
                         // Quit (always works)
@@ -232,68 +390,64 @@ async fn run_app(
                             }
                         }
                         // Speed test
-                        KeyCode::Char('s') | KeyCode::Char('S') => {
-                            if !app.show_settings
-                                && app.speedtest.is_none()
-                                && app.portscan.is_none()
-                            {
-                                app.start_speedtest().await?;
-                            }
+                        KeyCode::Char('s') | KeyCode::Char('S') if app.shortcuts_enabled() => {
+                            app.start_speedtest().await?;
                         }
                         // Port scan
-                        KeyCode::Char('p') | KeyCode::Char('P') => {
-                            if !app.show_settings
-                                && app.speedtest.is_none()
-                                && app.portscan.is_none()
-                            {
-                                app.start_portscan().await?;
-                            }
+                        KeyCode::Char('p') | KeyCode::Char('P') if app.shortcuts_enabled() => {
+                            app.start_portscan().await?;
                         }
                         // Other shortcuts (only when not in overlays)
-                        KeyCode::Char('j') | KeyCode::Char('J') => {
-                            if !app.show_settings
-                                && app.speedtest.is_none()
-                                && app.portscan.is_none()
-                            {
-                                app.toggle_jitter_panel();
-                            }
+                        KeyCode::Char('j') | KeyCode::Char('J') if app.shortcuts_enabled() => {
+                            app.toggle_jitter_panel();
                         }
-                        KeyCode::Char('h') | KeyCode::Char('H') => {
-                            if !app.show_settings
-                                && app.speedtest.is_none()
-                                && app.portscan.is_none()
-                            {
-                                app.toggle_history_panel();
-                            }
+                        KeyCode::Char('h') | KeyCode::Char('H') if app.shortcuts_enabled() => {
+                            app.toggle_history_panel();
                         }
-                        KeyCode::Char('r') | KeyCode::Char('R') => {
-                            if !app.show_settings
-                                && app.speedtest.is_none()
-                                && app.portscan.is_none()
-                            {
-                                app.reset_stats();
-                            }
+                        KeyCode::Char('r') | KeyCode::Char('R') if app.shortcuts_enabled() => {
+                            app.reset_stats();
                         }
                         // Web Check
-                        KeyCode::Char('w') | KeyCode::Char('W') => {
-                            if !app.show_settings
-                                && app.speedtest.is_none()
-                                && app.portscan.is_none()
-                            {
-                                app.toggle_web_check().await;
-                            }
+                        KeyCode::Char('w') | KeyCode::Char('W') if app.shortcuts_enabled() => {
+                            app.toggle_web_check().await;
+                        }
+
+                        KeyCode::Char('t') | KeyCode::Char('T') if app.shortcuts_enabled() => {
+                            app.toggle_traceroute();
+                        }
+                        KeyCode::Char('m') | KeyCode::Char('M') if app.shortcuts_enabled() => {
+                            app.start_mtu_discovery();
+                        }
+
+                        // Multi-target controls
+                        KeyCode::Char('a') | KeyCode::Char('A') if app.shortcuts_enabled() => {
+                            app.open_add_target();
+                        }
+                        KeyCode::Char('x') | KeyCode::Char('X') if app.shortcuts_enabled() => {
+                            app.remove_target();
+                        }
+                        KeyCode::Char('v') | KeyCode::Char('V') if app.shortcuts_enabled() => {
+                            app.toggle_view();
+                        }
+                        KeyCode::Tab if !app.show_settings => {
+                            app.next_target();
+                        }
+                        KeyCode::BackTab if !app.show_settings => {
+                            app.prev_target();
                         }
 
                         // Dynamic Controls (Arrow Keys)
+                        KeyCode::Right if app.show_settings => {
+                            app.settings_adjust_selected(1);
+                        }
+                        KeyCode::Left if app.show_settings => {
+                            app.settings_adjust_selected(-1);
+                        }
                         KeyCode::Right => {
-                            if !app.show_settings {
-                                app.increase_history();
-                            }
+                            app.increase_history();
                         }
                         KeyCode::Left => {
-                            if !app.show_settings {
-                                app.decrease_history();
-                            }
+                            app.decrease_history();
                         }
                         KeyCode::Up => {
                             if app.show_settings {
@@ -312,13 +466,8 @@ async fn run_app(
                         KeyCode::Enter if app.show_settings => {
                             app.settings_toggle_selected();
                         }
-                        KeyCode::Enter => {
-                            if !app.show_settings
-                                && app.speedtest.is_none()
-                                && app.portscan.is_none()
-                            {
-                                app.toggle_diagnostics();
-                            }
+                        KeyCode::Enter if app.shortcuts_enabled() => {
+                            app.toggle_diagnostics();
                         }
                         KeyCode::Char(c) if app.show_settings && c.is_ascii_digit() => {
                             if let Some(n) = c.to_digit(10) {
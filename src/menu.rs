@@ -183,6 +183,7 @@ impl MenuApp {
         }
     }
 
+    #[allow(clippy::collapsible_match)]
     fn move_horizontal(&mut self, delta: i32) {
         match self.selected_section {
             SelectionSection::History => {
@@ -442,10 +443,10 @@ impl MenuApp {
                 .direction(Direction::Vertical)
                 .margin(1)
                 .constraints([
-                    Constraint::Length(2), // Intro
-                    Constraint::Length(8), // Startup Controls
-                    Constraint::Length(8), // App Controls
-                    Constraint::Min(1),    // Footer
+                    Constraint::Length(2),  // Intro
+                    Constraint::Length(8),  // Startup Controls
+                    Constraint::Length(12), // App Controls
+                    Constraint::Min(1),     // Footer
                 ])
                 .split(help_block.inner(help_area));
 
@@ -518,6 +519,22 @@ impl MenuApp {
                     Span::styled("  R          ", Style::default().fg(self.theme.fg)),
                     Span::raw("Reset Statistics"),
                 ]),
+                Line::from(vec![
+                    Span::styled("  A / X / TAB", Style::default().fg(self.theme.fg)),
+                    Span::raw(" Add / Remove / Switch Target"),
+                ]),
+                Line::from(vec![
+                    Span::styled("  V          ", Style::default().fg(self.theme.fg)),
+                    Span::raw("Toggle Overview of All Targets"),
+                ]),
+                Line::from(vec![
+                    Span::styled("  M          ", Style::default().fg(self.theme.fg)),
+                    Span::raw("Discover Path MTU"),
+                ]),
+                Line::from(vec![
+                    Span::styled("  T          ", Style::default().fg(self.theme.fg)),
+                    Span::raw("Traceroute / MTR View"),
+                ]),
             ];
             f.render_widget(
                 Paragraph::new(app_controls).style(Style::default().fg(self.theme.low)),
@@ -0,0 +1,511 @@
+use crate::http::{self, Request, Response};
+use crate::network::{
+    NetworkStats, PingResult, ProbeMethod, ReplyKind, SpeedTestState, WebCheckStatus,
+};
+use crate::probe_log::probe_name;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+/// Upper bounds of the RTT histogram buckets, in seconds.
+const RTT_BUCKETS: [f64; 13] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Cumulative: every observation at or below the matching bound
+    buckets: [u64; RTT_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(RTT_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Everything exported for one target. Counters and the histogram count
+/// every result since the exporter started and are not cleared by a stats
+/// reset, so rates stay correct; the gauges come from the latest stats.
+#[derive(Debug)]
+struct TargetMetrics {
+    ip: IpAddr,
+    probe: ProbeMethod,
+    rtt: Histogram,
+    sent: u64,
+    lost: u64,
+    late: u64,
+    duplicate: u64,
+    stats: Option<NetworkStats>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    targets: BTreeMap<String, TargetMetrics>,
+    speedtest: Option<(SpeedTestState, DateTime<Utc>)>,
+}
+
+/// The `--metrics` endpoint: serves the latest results in the Prometheus
+/// text exposition format.
+pub struct Metrics {
+    registry: Arc<Mutex<Registry>>,
+    addr: SocketAddr,
+}
+
+impl Metrics {
+    pub async fn start(addr: SocketAddr) -> Result<Self> {
+        let registry = Arc::new(Mutex::new(Registry::default()));
+        let shared = Arc::clone(&registry);
+        let addr = http::serve(addr, "metrics", move |request| {
+            let response = handle(&request, &shared.lock().unwrap_or_else(|e| e.into_inner()));
+            async move { response }
+        })
+        .await?;
+        Ok(Self { registry, addr })
+    }
+
+    /// Where the endpoint listens, for the startup message.
+    pub fn describe(&self) -> String {
+        format!("Serving Prometheus metrics on http://{}/metrics", self.addr)
+    }
+
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.registry.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn ping(&self, target: &str, ip: IpAddr, probe: ProbeMethod, result: &PingResult) {
+        let mut registry = self.registry();
+        let metrics = registry
+            .targets
+            .entry(target.to_string())
+            .or_insert_with(|| TargetMetrics {
+                ip,
+                probe,
+                rtt: Histogram::default(),
+                sent: 0,
+                lost: 0,
+                late: 0,
+                duplicate: 0,
+                stats: None,
+            });
+        // The probe can fall back after the first result
+        metrics.probe = probe;
+        match result {
+            PingResult::Success(reply) => match reply.kind {
+                ReplyKind::Late => metrics.late += 1,
+                ReplyKind::Duplicate => metrics.duplicate += 1,
+                ReplyKind::OnTime | ReplyKind::Reordered => {
+                    metrics.sent += 1;
+                    metrics.rtt.observe(reply.rtt_ms / 1000.0);
+                }
+            },
+            PingResult::Timeout { .. } => {
+                metrics.sent += 1;
+                metrics.lost += 1;
+            }
+            PingResult::WebCheck { .. } => {}
+        }
+    }
+
+    /// Latest statistics for the gauges of a target that has results.
+    pub fn stats(&self, target: &str, stats: &NetworkStats) {
+        if let Some(metrics) = self.registry().targets.get_mut(target) {
+            metrics.stats = Some(stats.clone());
+        }
+    }
+
+    /// Stop exporting a target that is no longer monitored, so its series
+    /// don't linger with their last values.
+    pub fn remove_target(&self, target: &str) {
+        self.registry().targets.remove(target);
+    }
+
+    pub fn speedtest(&self, state: &SpeedTestState) {
+        if matches!(
+            state,
+            SpeedTestState::Complete { .. } | SpeedTestState::Error(_)
+        ) {
+            self.registry().speedtest = Some((state.clone(), Utc::now()));
+        }
+    }
+}
+
+fn handle(request: &Request, registry: &Registry) -> Response {
+    if request.method != "GET" && request.method != "HEAD" {
+        return Response::text(405, "Method Not Allowed\n");
+    }
+    match request.path.as_str() {
+        "/metrics" => Response::new(200, CONTENT_TYPE, render(registry)),
+        "/" => Response::new(
+            200,
+            "text/html; charset=utf-8",
+            "<html><body><h1>rping</h1><p><a href=\"/metrics\">Metrics</a></p></body></html>\n",
+        ),
+        _ => Response::not_found(),
+    }
+}
+
+/// Escape a label value as the exposition format requires.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Writes metric families, each header once before its samples.
+struct Exposition {
+    out: String,
+}
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {name} {help}");
+        let _ = writeln!(self.out, "# TYPE {name} {kind}");
+    }
+
+    fn sample(&mut self, name: &str, labels: &str, value: f64) {
+        if labels.is_empty() {
+            let _ = writeln!(self.out, "{name} {value}");
+        } else {
+            let _ = writeln!(self.out, "{name}{{{labels}}} {value}");
+        }
+    }
+
+    /// One counter family with a sample for every target.
+    fn counter(
+        &mut self,
+        registry: &Registry,
+        name: &str,
+        help: &str,
+        value: impl Fn(&TargetMetrics) -> u64,
+    ) {
+        self.family(name, "counter", help);
+        for (target, metrics) in &registry.targets {
+            let labels = format!("target=\"{}\"", label(target));
+            self.sample(name, &labels, value(metrics) as f64);
+        }
+    }
+
+    /// One gauge family with a sample for every target that has a value.
+    fn per_target(
+        &mut self,
+        registry: &Registry,
+        name: &str,
+        help: &str,
+        value: impl Fn(&NetworkStats) -> Option<f64>,
+    ) {
+        self.family(name, "gauge", help);
+        for (target, metrics) in &registry.targets {
+            if let Some(value) = metrics.stats.as_ref().and_then(&value) {
+                self.sample(name, &format!("target=\"{}\"", label(target)), value);
+            }
+        }
+    }
+}
+
+fn render(registry: &Registry) -> String {
+    let mut ex = Exposition { out: String::new() };
+
+    ex.family(
+        "rping_target_info",
+        "gauge",
+        "Monitored target with its address and probe method.",
+    );
+    for (target, metrics) in &registry.targets {
+        let labels = format!(
+            "target=\"{}\",ip=\"{}\",probe=\"{}\"",
+            label(target),
+            metrics.ip,
+            probe_name(metrics.probe)
+        );
+        ex.sample("rping_target_info", &labels, 1.0);
+    }
+
+    ex.family(
+        "rping_rtt_seconds",
+        "histogram",
+        "Round-trip time of answered probes.",
+    );
+    for (target, metrics) in &registry.targets {
+        let target = format!("target=\"{}\"", label(target));
+        for (count, bound) in metrics.rtt.buckets.iter().zip(RTT_BUCKETS) {
+            let labels = format!("{target},le=\"{bound}\"");
+            ex.sample("rping_rtt_seconds_bucket", &labels, *count as f64);
+        }
+        let labels = format!("{target},le=\"+Inf\"");
+        ex.sample(
+            "rping_rtt_seconds_bucket",
+            &labels,
+            metrics.rtt.count as f64,
+        );
+        ex.sample("rping_rtt_seconds_sum", &target, metrics.rtt.sum);
+        ex.sample("rping_rtt_seconds_count", &target, metrics.rtt.count as f64);
+    }
+
+    ex.counter(
+        registry,
+        "rping_probes_sent_total",
+        "Probes that were answered or timed out.",
+        |m| m.sent,
+    );
+    ex.counter(
+        registry,
+        "rping_probes_lost_total",
+        "Probes that timed out.",
+        |m| m.lost,
+    );
+    ex.counter(
+        registry,
+        "rping_late_replies_total",
+        "Replies that arrived after their probe timed out.",
+        |m| m.late,
+    );
+    ex.counter(
+        registry,
+        "rping_duplicate_replies_total",
+        "Duplicate replies.",
+        |m| m.duplicate,
+    );
+
+    ex.per_target(
+        registry,
+        "rping_up",
+        "1 if the most recent probe was answered.",
+        |s| {
+            Some(if s.current_response.is_some() {
+                1.0
+            } else {
+                0.0
+            })
+        },
+    );
+    ex.per_target(
+        registry,
+        "rping_last_rtt_seconds",
+        "Round-trip time of the most recent probe.",
+        |s| s.current_response.map(|ms| ms / 1000.0),
+    );
+    ex.per_target(
+        registry,
+        "rping_jitter_seconds",
+        "RFC 3550 interarrival jitter.",
+        |s| Some(s.jitter / 1000.0),
+    );
+    ex.per_target(
+        registry,
+        "rping_loss_ratio",
+        "Share of lost probes in the graph window.",
+        |s| Some(s.window.loss_pct / 100.0),
+    );
+    ex.per_target(
+        registry,
+        "rping_mos",
+        "Estimated Mean Opinion Score for a voice call (1-4.5).",
+        |s| Some(s.mos),
+    );
+    ex.per_target(
+        registry,
+        "rping_outages",
+        "Outages since the session started or was reset.",
+        |s| Some(s.outages.count as f64),
+    );
+
+    ex.family(
+        "rping_web_check_up",
+        "gauge",
+        "1 if the last TCP connect to the port succeeded.",
+    );
+    web_checks(
+        &mut ex,
+        registry,
+        "rping_web_check_up",
+        |status| match status {
+            WebCheckStatus::Untested => None,
+            WebCheckStatus::Success(_) => Some(1.0),
+            _ => Some(0.0),
+        },
+    );
+    ex.family(
+        "rping_web_check_seconds",
+        "gauge",
+        "Connect time of the last successful web check.",
+    );
+    web_checks(
+        &mut ex,
+        registry,
+        "rping_web_check_seconds",
+        |status| match status {
+            WebCheckStatus::Success(ms) => Some(ms / 1000.0),
+            _ => None,
+        },
+    );
+
+    if let Some((state, at)) = &registry.speedtest {
+        let (download, upload, success) = match state {
+            SpeedTestState::Complete {
+                download_mbps,
+                upload_mbps,
+                ..
+            } => (Some(*download_mbps), Some(*upload_mbps), 1.0),
+            _ => (None, None, 0.0),
+        };
+        ex.family(
+            "rping_speedtest_success",
+            "gauge",
+            "1 if the last speed test completed.",
+        );
+        ex.sample("rping_speedtest_success", "", success);
+        ex.family(
+            "rping_speedtest_timestamp_seconds",
+            "gauge",
+            "When the last speed test ended.",
+        );
+        ex.sample(
+            "rping_speedtest_timestamp_seconds",
+            "",
+            at.timestamp_millis() as f64 / 1000.0,
+        );
+        if let (Some(download), Some(upload)) = (download, upload) {
+            ex.family(
+                "rping_speedtest_download_bits_per_second",
+                "gauge",
+                "Download rate of the last speed test.",
+            );
+            ex.sample(
+                "rping_speedtest_download_bits_per_second",
+                "",
+                download * 1e6,
+            );
+            ex.family(
+                "rping_speedtest_upload_bits_per_second",
+                "gauge",
+                "Upload rate of the last speed test.",
+            );
+            ex.sample("rping_speedtest_upload_bits_per_second", "", upload * 1e6);
+        }
+    }
+
+    ex.out
+}
+
+/// Samples for ports 80 and 443 of every target.
+fn web_checks(
+    ex: &mut Exposition,
+    registry: &Registry,
+    name: &str,
+    value: impl Fn(&WebCheckStatus) -> Option<f64>,
+) {
+    for (target, metrics) in &registry.targets {
+        let Some(stats) = &metrics.stats else {
+            continue;
+        };
+        for (port, status) in [(80, &stats.tcp_port_80), (443, &stats.tcp_port_443)] {
+            if let Some(value) = value(status) {
+                let labels = format!("target=\"{}\",port=\"{port}\"", label(target));
+                ex.sample(name, &labels, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::ProbeReply;
+    use chrono::Local;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    fn reply(rtt_ms: f64) -> PingResult {
+        PingResult::Success(ProbeReply {
+            seq: 1,
+            sent: Local::now(),
+            rtt_ms,
+            ttl: Some(57),
+            size: 64,
+            source: "192.0.2.1".parse().unwrap(),
+            kind: ReplyKind::OnTime,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let metrics = Metrics::start("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        for result in [
+            reply(3.0),
+            reply(30.0),
+            PingResult::Timeout {
+                seq: 3,
+                sent: Local::now(),
+            },
+        ] {
+            metrics.ping("say \"hi\"", ip, ProbeMethod::Icmp, &result);
+        }
+        let stats = NetworkStats {
+            tcp_port_443: WebCheckStatus::Success(20.0),
+            ..NetworkStats::default()
+        };
+        metrics.stats("say \"hi\"", &stats);
+
+        let mut stream = TcpStream::connect(metrics.addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+        let target = r#"target="say \"hi\"""#;
+        for line in [
+            format!(r#"rping_target_info{{{target},ip="192.0.2.1",probe="icmp"}} 1"#),
+            format!(r#"rping_rtt_seconds_bucket{{{target},le="0.0025"}} 0"#),
+            format!(r#"rping_rtt_seconds_bucket{{{target},le="0.005"}} 1"#),
+            format!(r#"rping_rtt_seconds_bucket{{{target},le="+Inf"}} 2"#),
+            format!(r#"rping_rtt_seconds_count{{{target}}} 2"#),
+            format!(r#"rping_probes_sent_total{{{target}}} 3"#),
+            format!(r#"rping_probes_lost_total{{{target}}} 1"#),
+            format!(r#"rping_web_check_up{{{target},port="443"}} 1"#),
+        ] {
+            assert!(response.contains(&line), "missing {line} in\n{response}");
+        }
+        // Untested web checks and a missing speed test are left out
+        assert!(!response.contains(r#"port="80""#));
+        assert!(!response.contains("rping_speedtest_success"));
+    }
+
+    #[tokio::test]
+    async fn test_removed_target_is_not_exported() {
+        let metrics = Metrics::start("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        for target in ["kept", "removed"] {
+            metrics.ping(target, ip, ProbeMethod::Icmp, &reply(3.0));
+            metrics.stats(target, &NetworkStats::default());
+        }
+        metrics.remove_target("removed");
+
+        let text = render(&metrics.registry());
+        assert!(text.contains(r#"rping_probes_sent_total{target="kept"} 1"#));
+        assert!(!text.contains(r#"target="removed""#), "{text}");
+    }
+}
@@ -0,0 +1,398 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use socket2::Type;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use surge_ping::{AsyncSocket, Config, IcmpPacket, Icmpv4Packet, Icmpv6Packet, ICMP};
+use tracing::{debug, trace};
+
+const ICMPV4_ECHO_REQUEST: u8 = 8;
+const ICMPV4_ECHO_REPLY: u8 = 0;
+const ICMPV4_UNREACHABLE: u8 = 3;
+const ICMPV4_TIME_EXCEEDED: u8 = 11;
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+const ICMPV6_ECHO_REPLY: u8 = 129;
+const ICMPV6_UNREACHABLE: u8 = 1;
+const ICMPV6_TIME_EXCEEDED: u8 = 3;
+
+/// Largest possible IP packet. Receive buffers are this big so replies to
+/// jumbo payloads are never truncated.
+pub const MAX_PACKET: usize = 65535;
+
+/// How long answered and expired sequence numbers are remembered, so that late
+/// and duplicate replies can still be recognised.
+const RECENT_WINDOW: Duration = Duration::from_secs(60);
+
+/// What kind of answer came back for one of our echo requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseKind {
+    EchoReply,
+    /// A router dropped the request because its TTL ran out
+    TimeExceeded,
+    /// A router or the target reported the destination unreachable
+    Unreachable,
+}
+
+/// An answer read off the socket, before it is matched to a probe.
+#[derive(Debug, Clone)]
+pub struct EchoReply {
+    pub seq: u16,
+    pub ttl: Option<u8>,
+    pub size: usize,
+    pub source: IpAddr,
+    pub kind: ResponseKind,
+}
+
+/// ICMP Echo socket that sends requests and hands back raw replies without
+/// waiting on them, so replies can arrive late, twice or out of order.
+pub struct EchoSocket {
+    socket: AsyncSocket,
+    ident: u16,
+    addr: IpAddr,
+}
+
+impl EchoSocket {
+    pub fn open(addr: IpAddr, sock_type: Type) -> Result<Self> {
+        let kind = if addr.is_ipv6() { ICMP::V6 } else { ICMP::V4 };
+        let config = Config::builder()
+            .kind(kind)
+            .sock_type_hint(sock_type)
+            .build();
+        let socket = AsyncSocket::new(&config).map_err(|e| {
+            debug!("Failed to open {:?} ICMP socket: {}", sock_type, e);
+            anyhow::anyhow!("Network permission error: {e}")
+        })?;
+        // surge-ping silently switches socket type if the hint fails, so check what we got
+        let actual = socket.get_type();
+        if actual != sock_type {
+            debug!("Requested {:?} ICMP socket but got {:?}", sock_type, actual);
+            anyhow::bail!("{sock_type:?} ICMP socket unavailable");
+        }
+        Ok(Self {
+            socket,
+            ident: rand::random(),
+            addr,
+        })
+    }
+
+    pub fn sock_type(&self) -> Type {
+        self.socket.get_type()
+    }
+
+    pub async fn send(&self, seq: u16, payload: &[u8]) -> io::Result<()> {
+        let mut packet = echo_request(self.addr, self.sock_type(), self.ident, seq, payload);
+        self.socket
+            .send_to(&mut packet, &SocketAddr::new(self.addr, 0))
+            .await?;
+        Ok(())
+    }
+
+    /// Set the IP TTL (hop limit for IPv6) used by subsequent requests.
+    pub fn set_ttl(&self, ttl: u8) -> io::Result<()> {
+        #[cfg(unix)]
+        // SAFETY: the fd stays open for as long as `self.socket` lives
+        let fd = unsafe { std::os::fd::BorrowedFd::borrow_raw(self.socket.get_native_sock()) };
+        #[cfg(windows)]
+        // SAFETY: the socket stays open for as long as `self.socket` lives
+        let fd = unsafe {
+            std::os::windows::io::BorrowedSocket::borrow_raw(self.socket.get_native_sock())
+        };
+        let sock = socket2::SockRef::from(&fd);
+        if self.addr.is_ipv6() {
+            sock.set_unicast_hops_v6(u32::from(ttl))
+        } else {
+            sock.set_ttl_v4(u32::from(ttl))
+        }
+    }
+
+    /// Set the Don't-Fragment bit on everything sent from this socket, so
+    /// oversized requests fail instead of being fragmented.
+    #[cfg(target_os = "linux")]
+    pub fn set_dont_fragment(&self) -> io::Result<()> {
+        let (level, name, value) = if self.addr.is_ipv6() {
+            (
+                libc::IPPROTO_IPV6,
+                libc::IPV6_MTU_DISCOVER,
+                libc::IPV6_PMTUDISC_DO,
+            )
+        } else {
+            (
+                libc::IPPROTO_IP,
+                libc::IP_MTU_DISCOVER,
+                libc::IP_PMTUDISC_DO,
+            )
+        };
+        // SAFETY: the fd is owned by `self.socket` and `value` outlives the call
+        let ret = unsafe {
+            libc::setsockopt(
+                self.socket.get_native_sock(),
+                level,
+                name,
+                &value as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn set_dont_fragment(&self) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Don't-Fragment probes are only supported on Linux",
+        ))
+    }
+
+    /// Send a single request and wait up to `timeout` for its reply. Returns
+    /// `false` if the request could not be sent (e.g. too big with DF set) or
+    /// was not answered in time.
+    pub async fn echo(&self, seq: u16, payload: &[u8], timeout: Duration) -> bool {
+        if let Err(e) = self.send(seq, payload).await {
+            trace!(
+                "Echo (seq={}, {} bytes) not sent: {}",
+                seq,
+                payload.len(),
+                e
+            );
+            return false;
+        }
+        let mut buf = vec![0u8; payload.len() + 128];
+        let wait = async {
+            loop {
+                match self.recv(&mut buf).await {
+                    Ok(Some(reply))
+                        if reply.seq == seq && reply.kind == ResponseKind::EchoReply =>
+                    {
+                        return true
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        debug!("ICMP receive error: {}", e);
+                        return false;
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.unwrap_or(false)
+    }
+
+    /// Wait for the next packet and decode it. Returns `None` for anything that
+    /// is not an answer to one of our requests. ICMP errors (time exceeded,
+    /// unreachable) only reach raw sockets.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<Option<EchoReply>> {
+        let (len, from) = self.socket.recv_from(buf).await?;
+        let message = &buf[..len];
+        let packet = match from.ip() {
+            IpAddr::V4(source) => {
+                let local = match self.socket.local_addr()?.ip() {
+                    IpAddr::V4(local) => local,
+                    IpAddr::V6(_) => return Ok(None),
+                };
+                Icmpv4Packet::decode(message, self.sock_type(), source, local).map(IcmpPacket::V4)
+            }
+            IpAddr::V6(source) => Icmpv6Packet::decode(message, source).map(IcmpPacket::V6),
+        };
+        let packet = match packet {
+            Ok(packet) => packet,
+            Err(e) => {
+                trace!("Ignoring ICMP packet from {}: {:?}", from, e);
+                return Ok(None);
+            }
+        };
+
+        let (kind, ttl, size, source) = match &packet {
+            IcmpPacket::V4(p) => (
+                match p.get_icmp_type().0 {
+                    ICMPV4_ECHO_REPLY => ResponseKind::EchoReply,
+                    ICMPV4_TIME_EXCEEDED => ResponseKind::TimeExceeded,
+                    ICMPV4_UNREACHABLE => ResponseKind::Unreachable,
+                    _ => return Ok(None),
+                },
+                p.get_ttl(),
+                p.get_size(),
+                IpAddr::V4(p.get_source()),
+            ),
+            IcmpPacket::V6(p) => (
+                match p.get_icmpv6_type().0 {
+                    ICMPV6_ECHO_REPLY => ResponseKind::EchoReply,
+                    ICMPV6_TIME_EXCEEDED => ResponseKind::TimeExceeded,
+                    ICMPV6_UNREACHABLE => ResponseKind::Unreachable,
+                    _ => return Ok(None),
+                },
+                None,
+                p.get_size(),
+                IpAddr::V6(p.get_source()),
+            ),
+        };
+        // Raw sockets see every ICMP packet on the host; datagram sockets are
+        // already filtered by the kernel, which also rewrites the identifier.
+        // For ICMP errors the identifier comes from the quoted original request.
+        if self.sock_type() == Type::RAW && packet.get_identifier().0 != self.ident {
+            return Ok(None);
+        }
+
+        Ok(Some(EchoReply {
+            seq: packet.get_sequence().0,
+            ttl,
+            size,
+            source,
+            kind,
+        }))
+    }
+}
+
+/// Build an ICMP Echo Request. The kernel fills in the checksum for ICMPv6 and
+/// both identifier and checksum for Linux datagram sockets.
+fn echo_request(addr: IpAddr, sock_type: Type, ident: u16, seq: u16, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(8 + payload.len());
+    packet.push(if addr.is_ipv6() {
+        ICMPV6_ECHO_REQUEST
+    } else {
+        ICMPV4_ECHO_REQUEST
+    });
+    packet.push(0); // code
+    packet.extend_from_slice(&[0, 0]); // checksum
+    packet.extend_from_slice(&ident.to_be_bytes());
+    packet.extend_from_slice(&seq.to_be_bytes());
+    packet.extend_from_slice(payload);
+
+    if addr.is_ipv4() && sock_type == Type::RAW {
+        let checksum = internet_checksum(&packet);
+        packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+    packet
+}
+
+/// RFC 1071 ones' complement checksum.
+fn internet_checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|pair| u32::from(u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])))
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// How a reply relates to the probes still in flight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplyKind {
+    /// Answered within the timeout, in order
+    #[default]
+    OnTime,
+    /// Answered within the timeout, but after a later probe was answered
+    Reordered,
+    /// Answered after the probe had already been reported as a timeout
+    Late,
+    /// A second reply for a probe that was already answered
+    Duplicate,
+}
+
+/// Tracks outstanding sequence numbers so replies can be classified.
+#[derive(Debug, Default)]
+pub struct SeqTracker {
+    outstanding: HashMap<u16, Instant>,
+    expired: HashMap<u16, Instant>,
+    answered: HashMap<u16, Instant>,
+    highest_answered: Option<u16>,
+}
+
+impl SeqTracker {
+    pub fn sent(&mut self, seq: u16, at: Instant) {
+        // A wrapped sequence number replaces whatever was remembered for it
+        self.expired.remove(&seq);
+        self.answered.remove(&seq);
+        self.outstanding.insert(seq, at);
+    }
+
+    /// Classify a reply and return its round-trip time, or `None` if the
+    /// sequence number is unknown (never sent or long forgotten).
+    pub fn reply(&mut self, seq: u16, at: Instant) -> Option<(ReplyKind, Duration)> {
+        if let Some(sent) = self.outstanding.remove(&seq) {
+            self.answered.insert(seq, sent);
+            // Wrapping comparison: a reply is reordered if a later probe already came back
+            let kind = match self.highest_answered {
+                Some(highest) if (highest.wrapping_sub(seq) as i16) > 0 => ReplyKind::Reordered,
+                _ => {
+                    self.highest_answered = Some(seq);
+                    ReplyKind::OnTime
+                }
+            };
+            return Some((kind, at.saturating_duration_since(sent)));
+        }
+        if let Some(sent) = self.expired.remove(&seq) {
+            self.answered.insert(seq, sent);
+            return Some((ReplyKind::Late, at.saturating_duration_since(sent)));
+        }
+        self.answered
+            .get(&seq)
+            .map(|sent| (ReplyKind::Duplicate, at.saturating_duration_since(*sent)))
+    }
+
+    /// Move probes older than `timeout` to the expired set and return their
+    /// sequence numbers and send times in send order.
+    pub fn expire(&mut self, now: Instant, timeout: Duration) -> Vec<(u16, Instant)> {
+        let mut timed_out: Vec<(u16, Instant)> = self
+            .outstanding
+            .iter()
+            .filter(|(_, sent)| now.saturating_duration_since(**sent) >= timeout)
+            .map(|(seq, sent)| (*seq, *sent))
+            .collect();
+        timed_out.sort_by_key(|(_, sent)| *sent);
+        for (seq, sent) in &timed_out {
+            self.outstanding.remove(seq);
+            self.expired.insert(*seq, *sent);
+        }
+
+        let keep = |sent: &mut Instant| now.saturating_duration_since(*sent) < RECENT_WINDOW;
+        self.expired.retain(|_, sent| keep(sent));
+        self.answered.retain(|_, sent| keep(sent));
+
+        timed_out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_of_echo_request() {
+        let packet = echo_request("192.0.2.1".parse().unwrap(), Type::RAW, 0x1234, 1, &[0; 8]);
+        // A packet carrying its own checksum sums to zero
+        assert_eq!(internet_checksum(&packet), 0);
+    }
+
+    #[test]
+    fn test_reply_classification() {
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let mut tracker = SeqTracker::default();
+
+        tracker.sent(1, ms(0));
+        tracker.sent(2, ms(100));
+        tracker.sent(3, ms(200));
+
+        assert_eq!(tracker.reply(2, ms(150)).unwrap().0, ReplyKind::OnTime);
+        assert_eq!(tracker.reply(1, ms(160)).unwrap().0, ReplyKind::Reordered);
+        assert_eq!(tracker.reply(2, ms(170)).unwrap().0, ReplyKind::Duplicate);
+
+        assert_eq!(
+            tracker.expire(ms(2300), Duration::from_secs(2)),
+            vec![(3, ms(200))]
+        );
+        let (kind, rtt) = tracker.reply(3, ms(2500)).unwrap();
+        assert_eq!(kind, ReplyKind::Late);
+        assert_eq!(rtt, Duration::from_millis(2300));
+
+        assert!(tracker.reply(42, ms(2600)).is_none());
+    }
+}
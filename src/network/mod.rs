@@ -1,26 +1,92 @@
+mod icmp;
+mod mtu;
+mod outage;
 mod ping;
 mod portscan;
+mod quality;
 mod speedtest;
+mod traceroute;
 
-pub use ping::{start_ping_task, PingCommand, PingMonitor, PingResult, WebCheckStatus};
+pub use mtu::{discover_path_mtu, PathMtu};
+pub use outage::{format_duration, OutageSummary, DEFAULT_OUTAGE_THRESHOLD};
+pub use ping::{
+    parse_payload_pattern, start_ping_task, PingCommand, PingMonitor, PingResult, ProbeMethod,
+    ProbeOptions, ProbeReply, ReplyKind, WebCheckStatus,
+};
 pub use portscan::{PortResult, PortScanner, PortStatus};
+pub use quality::{UseCase, Verdict};
+use serde::{Deserialize, Serialize};
 pub use speedtest::{SpeedTest, SpeedTestState};
+use std::net::IpAddr;
+pub use traceroute::Traceroute;
+
+/// RTT percentiles (ms).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+/// Probe counters and RTT summary over one span of probes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SpanStats {
+    /// Probes that completed (reply or timeout)
+    pub sent: u64,
+    pub received: u64,
+    pub loss_pct: f64,
+    pub avg_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    /// Population standard deviation of the RTTs
+    pub std_dev: f64,
+}
+
+impl SpanStats {
+    pub fn uptime_pct(&self) -> f64 {
+        if self.sent == 0 {
+            0.0
+        } else {
+            100.0 - self.loss_pct
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct NetworkStats {
     pub current_response: Option<f64>,
     pub current_avg: f64,
-    pub avg_response: f64,
-    pub min_response: f64,
-    pub max_response: f64,
-    pub uptime_pct: f64,
-    pub packet_loss_pct: f64,
+    /// Every probe since the session started or was last reset
+    pub lifetime: SpanStats,
+    /// Only the probes currently shown in the graph window
+    pub window: SpanStats,
+    /// RFC 3550 interarrival jitter (smoothed, as reported in RTCP)
     pub jitter: f64,
+    /// Mean absolute difference between consecutive RTTs in the window
+    pub mean_delta: f64,
+    /// RTT percentiles over the graph window
+    pub percentiles: Percentiles,
+    /// ITU-T G.107 E-model estimate for a G.711 call (0-100)
+    pub r_factor: f64,
+    /// Mean Opinion Score derived from `r_factor` (1.0-4.5)
+    pub mos: f64,
     pub stability: f64,
     pub quality: String,
-    pub total_pings: u64,
+    // Reply details
+    pub ttl: Option<u8>,
+    pub reply_size: Option<usize>,
+    pub route_changes: u64,
+    pub foreign_replies: u64,
+    pub last_foreign_source: Option<IpAddr>,
+    // Reply anomalies (ICMP only)
+    pub late_replies: u64,
+    pub duplicate_replies: u64,
+    pub reordered_replies: u64,
+    pub outages: OutageSummary,
     // New Metrics
     pub dns_duration: Option<f64>,
+    pub path_mtu: PathMtu,
     pub tcp_port_80: WebCheckStatus,
     pub tcp_port_443: WebCheckStatus,
 }
@@ -30,16 +96,26 @@ impl Default for NetworkStats {
         Self {
             current_response: None,
             current_avg: 0.0,
-            avg_response: 0.0,
-            min_response: 0.0,
-            max_response: 0.0,
-            uptime_pct: 0.0,
-            packet_loss_pct: 0.0,
+            lifetime: SpanStats::default(),
+            window: SpanStats::default(),
             jitter: 0.0,
+            mean_delta: 0.0,
+            percentiles: Percentiles::default(),
+            r_factor: 0.0,
+            mos: 1.0,
             stability: 100.0,
             quality: "UNKNOWN".to_string(),
-            total_pings: 0,
+            ttl: None,
+            reply_size: None,
+            route_changes: 0,
+            foreign_replies: 0,
+            last_foreign_source: None,
+            late_replies: 0,
+            duplicate_replies: 0,
+            reordered_replies: 0,
+            outages: OutageSummary::default(),
             dns_duration: None,
+            path_mtu: PathMtu::Unknown,
             tcp_port_80: WebCheckStatus::Untested,
             tcp_port_443: WebCheckStatus::Untested,
         }
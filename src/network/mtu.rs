@@ -0,0 +1,152 @@
+use super::icmp::EchoSocket;
+use anyhow::Result;
+use socket2::Type;
+use std::future::Future;
+use std::net::IpAddr;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Largest ICMP payload that fits in an unfragmented 64 KiB IP packet.
+const MAX_PAYLOAD_V4: usize = 65535 - 20 - 8;
+const MAX_PAYLOAD_V6: usize = 65535 - 40 - 8;
+
+/// Path MTU discovery state for one target.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum PathMtu {
+    #[default]
+    Unknown,
+    Discovering,
+    Found {
+        mtu: usize,
+        payload: usize,
+    },
+    Failed(String),
+}
+
+/// Find the largest ICMP payload that reaches `addr` with the
+/// Don't-Fragment bit set, and return the resulting path MTU.
+pub async fn discover_path_mtu(addr: IpAddr, timeout: Duration) -> Result<PathMtu> {
+    let socket = match EchoSocket::open(addr, Type::RAW) {
+        Ok(socket) => socket,
+        Err(_) => EchoSocket::open(addr, Type::DGRAM)
+            .map_err(|e| anyhow::anyhow!("Path MTU discovery needs an ICMP socket: {e}"))?,
+    };
+    socket.set_dont_fragment()?;
+
+    let (header, max_payload) = if addr.is_ipv6() {
+        (40 + 8, MAX_PAYLOAD_V6)
+    } else {
+        (20 + 8, MAX_PAYLOAD_V4)
+    };
+
+    let mut seq: u16 = 0;
+    let socket = &socket;
+    let probe = |size: usize| {
+        seq = seq.wrapping_add(1);
+        let (seq, payload) = (seq, vec![0u8; size]);
+        async move { socket.echo(seq, &payload, timeout).await }
+    };
+    let Some(payload) = largest_fitting(max_payload, probe).await else {
+        anyhow::bail!("{addr} does not answer ICMP Echo");
+    };
+
+    let mtu = payload + header;
+    info!("Path MTU to {} is {} ({} byte payload)", addr, mtu, payload);
+    Ok(PathMtu::Found { mtu, payload })
+}
+
+/// Binary-search the largest payload up to `max_payload` for which `probe`
+/// is answered. `None` if not even an empty payload gets through.
+async fn largest_fitting<F, Fut>(max_payload: usize, mut probe: F) -> Option<usize>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    if !fits(&mut probe, 0).await {
+        return None;
+    }
+
+    // Invariant: `good` fits, `bad` does not
+    let (mut good, mut bad) = (0, max_payload + 1);
+    while bad - good > 1 {
+        let mid = good + (bad - good) / 2;
+        if fits(&mut probe, mid).await {
+            good = mid;
+        } else {
+            bad = mid;
+        }
+        debug!("PMTU search: {}..{}", good, bad);
+    }
+    Some(good)
+}
+
+/// Whether `probe` gets an answer for `size` payload bytes.
+async fn fits<F, Fut>(probe: &mut F, size: usize) -> bool
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    // One retry so a single lost packet doesn't shrink the result
+    for _ in 0..2 {
+        if probe(size).await {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::collections::HashSet;
+
+    async fn search(max_payload: usize, limit: Option<usize>) -> Option<usize> {
+        largest_fitting(max_payload, |size| async move {
+            limit.is_some_and(|l| size <= l)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_search_boundaries() {
+        assert_eq!(search(MAX_PAYLOAD_V4, None).await, None);
+        assert_eq!(search(MAX_PAYLOAD_V4, Some(0)).await, Some(0));
+        assert_eq!(search(MAX_PAYLOAD_V4, Some(1)).await, Some(1));
+        assert_eq!(search(MAX_PAYLOAD_V4, Some(1472)).await, Some(1472));
+        assert_eq!(
+            search(MAX_PAYLOAD_V4, Some(MAX_PAYLOAD_V4)).await,
+            Some(MAX_PAYLOAD_V4)
+        );
+        assert_eq!(
+            search(MAX_PAYLOAD_V6, Some(MAX_PAYLOAD_V4)).await,
+            Some(MAX_PAYLOAD_V6)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_retries_lost_probes() {
+        // Every size is lost once: the retry still finds the real limit
+        let lost = std::cell::RefCell::new(HashSet::new());
+        let once = |size: usize| {
+            let fresh = lost.borrow_mut().insert(size);
+            async move { !fresh && size <= 1472 }
+        };
+        assert_eq!(largest_fitting(MAX_PAYLOAD_V4, once).await, Some(1472));
+
+        // Lost twice in a row counts as too big: drop both attempts at the
+        // first size that would fit
+        let dropped = Cell::new(None);
+        let twice = |size: usize| {
+            if size > 0 && size <= 1472 && dropped.get().is_none() {
+                dropped.set(Some(size));
+            }
+            let fits = size <= 1472 && dropped.get() != Some(size);
+            async move { fits }
+        };
+        let found = largest_fitting(MAX_PAYLOAD_V4, twice).await;
+        let dropped = dropped.get().unwrap();
+        assert!(dropped < 1472);
+        assert_eq!(found, Some(dropped - 1));
+    }
+}
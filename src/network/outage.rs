@@ -0,0 +1,200 @@
+use chrono::{DateTime, Local};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Consecutive timeouts that count as an outage unless configured otherwise.
+pub const DEFAULT_OUTAGE_THRESHOLD: u32 = 3;
+
+/// A run of consecutive timeouts long enough to count as an outage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outage {
+    /// When the first timeout of the run was seen
+    pub start: DateTime<Local>,
+    /// When the target answered again, `None` while the outage is ongoing
+    pub end: Option<DateTime<Local>>,
+    /// Probes lost during the outage
+    pub missed: u64,
+}
+
+impl Outage {
+    /// Length of the outage, measured up to `now` while it is still ongoing.
+    pub fn duration(&self, now: DateTime<Local>) -> Duration {
+        (self.end.unwrap_or(now) - self.start)
+            .to_std()
+            .unwrap_or_default()
+    }
+}
+
+/// Aggregate view over every outage of a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OutageSummary {
+    /// Outages including an ongoing one
+    pub count: usize,
+    pub longest: Duration,
+    /// Mean time to recovery over the outages that have ended
+    pub mttr: Option<Duration>,
+    /// How long the current outage has lasted so far
+    pub ongoing: Option<Duration>,
+}
+
+/// Turns the stream of probe outcomes into a list of outages. The list is
+/// kept for the whole session, independent of the graph window.
+#[derive(Debug, Clone)]
+pub struct OutageTracker {
+    pub threshold: u32,
+    run: u64,
+    run_start: Option<DateTime<Local>>,
+    outages: Vec<Outage>,
+}
+
+impl Default for OutageTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_OUTAGE_THRESHOLD)
+    }
+}
+
+impl OutageTracker {
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            run: 0,
+            run_start: None,
+            outages: Vec::new(),
+        }
+    }
+
+    pub fn timeout(&mut self, at: DateTime<Local>) {
+        self.run += 1;
+        let run = self.run;
+        let start = *self.run_start.get_or_insert(at);
+        if run == u64::from(self.threshold) {
+            warn!(
+                "Outage started at {} ({} consecutive timeouts)",
+                start.format("%H:%M:%S"),
+                run
+            );
+            self.outages.push(Outage {
+                start,
+                end: None,
+                missed: run,
+            });
+        } else if let Some(outage) = self.ongoing_mut() {
+            outage.missed = run;
+        }
+    }
+
+    /// Record a reply. Returns the outage it ended, if any.
+    pub fn reply(&mut self, at: DateTime<Local>) -> Option<&Outage> {
+        self.run = 0;
+        self.run_start = None;
+        let outage = self.ongoing_mut()?;
+        outage.end = Some(at);
+        info!(
+            "Outage ended after {} ({} probes lost)",
+            format_duration(outage.duration(at)),
+            outage.missed
+        );
+        self.outages.last()
+    }
+
+    fn ongoing_mut(&mut self) -> Option<&mut Outage> {
+        self.outages.last_mut().filter(|o| o.end.is_none())
+    }
+
+    pub fn outages(&self) -> &[Outage] {
+        &self.outages
+    }
+
+    pub fn summary(&self, now: DateTime<Local>) -> OutageSummary {
+        let mut summary = OutageSummary {
+            count: self.outages.len(),
+            ..OutageSummary::default()
+        };
+        let mut recovered = Duration::ZERO;
+        let mut resolved = 0u32;
+        for outage in &self.outages {
+            let duration = outage.duration(now);
+            summary.longest = summary.longest.max(duration);
+            if outage.end.is_some() {
+                recovered += duration;
+                resolved += 1;
+            } else {
+                summary.ongoing = Some(duration);
+            }
+        }
+        if resolved > 0 {
+            summary.mttr = Some(recovered / resolved);
+        }
+        summary
+    }
+
+    pub fn clear(&mut self) {
+        self.run = 0;
+        self.run_start = None;
+        self.outages.clear();
+    }
+}
+
+/// Compact human-readable duration, e.g. `850ms`, `12.5s`, `3m04s`, `1h02m`.
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else if secs >= 1 {
+        format!("{:.1}s", d.as_secs_f64())
+    } else {
+        format!("{}ms", d.as_millis())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_outage_needs_threshold_and_survives_recovery() {
+        let t0 = Local::now();
+        let at = |secs: i64| t0 + TimeDelta::seconds(secs);
+        let mut tracker = OutageTracker::new(3);
+
+        // Two lost probes are just loss
+        tracker.timeout(at(0));
+        tracker.timeout(at(1));
+        assert!(tracker.reply(at(2)).is_none());
+        assert!(tracker.outages().is_empty());
+
+        // Four in a row: an outage from the first timeout until the reply
+        for secs in 3..7 {
+            tracker.timeout(at(secs));
+        }
+        let summary = tracker.summary(at(8));
+        assert_eq!(summary.count, 1);
+        assert_eq!(summary.ongoing, Some(Duration::from_secs(5)));
+        assert_eq!(summary.mttr, None);
+
+        let ended = tracker.reply(at(9)).unwrap();
+        assert_eq!(ended.missed, 4);
+        assert_eq!(ended.duration(at(100)), Duration::from_secs(6));
+
+        for secs in 10..13 {
+            tracker.timeout(at(secs));
+        }
+        tracker.reply(at(12));
+        let summary = tracker.summary(at(20));
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.longest, Duration::from_secs(6));
+        assert_eq!(summary.mttr, Some(Duration::from_secs(4)));
+        assert_eq!(summary.ongoing, None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_duration(Duration::from_millis(12_500)), "12.5s");
+        assert_eq!(format_duration(Duration::from_secs(184)), "3m04s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h02m");
+    }
+}
@@ -1,16 +1,26 @@
-use super::NetworkStats;
+use super::icmp::{EchoReply, EchoSocket, ResponseKind, SeqTracker, MAX_PACKET};
+use super::mtu::PathMtu;
+use super::outage::{Outage, OutageTracker};
+use super::quality;
+use super::{NetworkStats, Percentiles, SpanStats};
 use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use socket2::Type;
 use std::collections::VecDeque;
+use std::fmt;
 use std::net::IpAddr;
-use std::time::Duration;
-use surge_ping::{Client, Config, PingIdentifier, PingSequence};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+pub use super::icmp::ReplyKind;
+
 use std::io;
 use tokio::net::TcpStream;
 use tracing::{debug, error, info, trace, warn};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum WebCheckStatus {
     Untested,
     Success(f64),
@@ -19,30 +29,148 @@ pub enum WebCheckStatus {
     Error(String),
 }
 
-#[derive(Debug)]
+/// How the background task measures round-trip time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ProbeMethod {
+    /// ICMP Echo via a raw socket (needs Administrator / CAP_NET_RAW).
+    /// When requested, falls back to `IcmpUnprivileged` and then TCP.
+    #[default]
+    Icmp,
+    /// ICMP Echo via a datagram socket (Linux `net.ipv4.ping_group_range`)
+    IcmpUnprivileged,
+    /// TCP handshake time to the given port, works without raw-socket privileges
+    Tcp(u16),
+}
+
+/// Port used when no ICMP socket kind can be opened at all.
+pub const FALLBACK_TCP_PORT: u16 = 443;
+
+impl fmt::Display for ProbeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeMethod::Icmp => write!(f, "ICMP"),
+            ProbeMethod::IcmpUnprivileged => write!(f, "ICMP (unprivileged)"),
+            ProbeMethod::Tcp(port) => write!(f, "TCP/{port}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum PingCommand {
     ToggleWebCheck(bool),
     SetInterval(u64),
+    SetProbeOptions(ProbeOptions),
     #[allow(dead_code)]
     Stop,
 }
 
+/// Details of a single answered probe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeReply {
+    pub seq: u16,
+    /// When the probe was sent
+    pub sent: DateTime<Local>,
+    pub rtt_ms: f64,
+    /// IP TTL of the reply (ICMPv4 only)
+    pub ttl: Option<u8>,
+    /// Reply size in bytes (0 for TCP probes)
+    pub size: usize,
+    /// Address the reply came from
+    pub source: IpAddr,
+    pub kind: ReplyKind,
+}
+
+/// Timeout and echo payload applied to every probe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeOptions {
+    /// How long a probe may go unanswered before it is reported as a timeout.
+    /// Also bounds TCP probes and web checks.
+    pub timeout: Duration,
+    /// ICMP Echo data (ignored by TCP probes)
+    pub payload: Vec<u8>,
+}
+
+impl ProbeOptions {
+    /// Build a payload of `size` bytes by repeating `pattern`.
+    pub fn new(timeout_ms: u64, size: usize, pattern: &[u8]) -> Self {
+        let pattern = if pattern.is_empty() {
+            &[0][..]
+        } else {
+            pattern
+        };
+        Self {
+            timeout: Duration::from_millis(timeout_ms),
+            payload: pattern.iter().copied().cycle().take(size).collect(),
+        }
+    }
+}
+
+impl Default for ProbeOptions {
+    fn default() -> Self {
+        Self::new(2000, 8, &[0])
+    }
+}
+
+/// Parse a payload pattern given as hex bytes, like `ping -p` (e.g. `ff`, `deadbeef`).
+pub fn parse_payload_pattern(input: &str) -> Result<Vec<u8>> {
+    let hex = input.trim().trim_start_matches("0x");
+    if hex.is_empty() || !hex.len().is_multiple_of(2) || hex.len() > 32 {
+        anyhow::bail!("Payload pattern must be 1 to 16 hex bytes, got '{input}'");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| anyhow::anyhow!("Invalid hex in payload pattern '{input}'"))
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 pub enum PingResult {
-    Success(f64),
-    Timeout,
+    Success(ProbeReply),
+    Timeout { seq: u16, sent: DateTime<Local> },
     WebCheck { port: u16, status: WebCheckStatus },
 }
 
+/// Reply details kept alongside each latency sample in the graph window.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SampleMeta {
+    pub ttl: Option<u8>,
+    pub size: Option<usize>,
+    /// TTL differs from the previous reply, which usually means the route changed
+    pub route_change: bool,
+}
+
 pub struct PingMonitor {
     target_addr: IpAddr,
     history: VecDeque<Option<f64>>,
+    /// Running aggregates over the successful samples in `history`
+    window: RttWindow,
+    meta: VecDeque<SampleMeta>,
     recent: VecDeque<f64>,
+    /// RFC 3550 interarrival jitter estimate, updated per reply
+    rfc_jitter: f64,
+    last_rtt: Option<f64>,
     max_history: usize,
     total_pings: u64,
     successful_pings: u64,
     failed_pings: u64,
+    /// RTT totals over every reply, unaffected by the window size
+    lifetime_rtt: RttTotals,
+    last_ttl: Option<u8>,
+    last_size: Option<usize>,
+    route_changes: u64,
+    foreign_replies: u64,
+    last_foreign_source: Option<IpAddr>,
+    late_replies: u64,
+    duplicate_replies: u64,
+    reordered_replies: u64,
+    pub outages: OutageTracker,
     pub dns_duration: Option<f64>,
+    pub path_mtu: PathMtu,
+    pub probe_method: ProbeMethod,
+    pub requested_probe_method: ProbeMethod,
     pub tcp_80: WebCheckStatus,
     pub tcp_443: WebCheckStatus,
 }
@@ -52,12 +180,29 @@ impl PingMonitor {
         Self {
             target_addr,
             history: VecDeque::with_capacity(max_history),
+            window: RttWindow::with_capacity(max_history),
+            meta: VecDeque::with_capacity(max_history),
             recent: VecDeque::with_capacity(10),
+            rfc_jitter: 0.0,
+            last_rtt: None,
             max_history,
             total_pings: 0,
             successful_pings: 0,
             failed_pings: 0,
+            lifetime_rtt: RttTotals::default(),
+            last_ttl: None,
+            last_size: None,
+            route_changes: 0,
+            foreign_replies: 0,
+            last_foreign_source: None,
+            late_replies: 0,
+            duplicate_replies: 0,
+            reordered_replies: 0,
+            outages: OutageTracker::default(),
             dns_duration: None,
+            path_mtu: PathMtu::Unknown,
+            probe_method: ProbeMethod::Icmp,
+            requested_probe_method: ProbeMethod::Icmp,
             tcp_80: WebCheckStatus::Untested,
             tcp_443: WebCheckStatus::Untested,
         }
@@ -76,31 +221,92 @@ impl PingMonitor {
 
         // If shrinking, truncate older data
         while self.history.len() > new_size {
-            self.history.pop_front();
+            self.drop_oldest();
         }
 
         // If growing, we just let it fill up naturally
     }
 
+    /// Outages are stamped with the send time of the probes, so a run of
+    /// timeouts starts when the first lost probe went out, not when it expired.
     pub fn process_result(&mut self, result: PingResult) {
-        self.total_pings += 1;
         match &result {
-            PingResult::Success(ms) => {
+            PingResult::Success(reply) if reply.kind == ReplyKind::Late => {
+                // Already counted as a timeout when it expired
+                debug!("Late reply (seq={}) after {:.2}ms", reply.seq, reply.rtt_ms);
+                self.late_replies += 1;
+            }
+            PingResult::Success(reply) if reply.kind == ReplyKind::Duplicate => {
+                debug!("Duplicate reply (seq={}) from {}", reply.seq, reply.source);
+                self.duplicate_replies += 1;
+            }
+            PingResult::Success(reply) => {
+                let ms = reply.rtt_ms;
                 trace!("Processing Ping Success: {:.2}ms", ms);
+                if reply.kind == ReplyKind::Reordered {
+                    debug!("Out-of-order reply (seq={})", reply.seq);
+                    self.reordered_replies += 1;
+                }
+                self.total_pings += 1;
                 self.successful_pings += 1;
-                self.history.push_back(Some(*ms));
-                self.recent.push_back(*ms);
+                self.lifetime_rtt.add(ms);
+                self.outages.reply(reply.sent);
+                self.history.push_back(Some(ms));
+                self.window.push(ms);
+                self.recent.push_back(ms);
                 if self.recent.len() > 10 {
                     self.recent.pop_front();
                 }
+                // J(i) = J(i-1) + (|D(i-1,i)| - J(i-1)) / 16 (RFC 3550 §6.4.1)
+                if let Some(prev) = self.last_rtt {
+                    self.rfc_jitter += ((ms - prev).abs() - self.rfc_jitter) / 16.0;
+                }
+                self.last_rtt = Some(ms);
+
+                if reply.source != self.target_addr {
+                    warn!(
+                        "Reply (seq={}) came from {} instead of {}",
+                        reply.seq, reply.source, self.target_addr
+                    );
+                    self.foreign_replies += 1;
+                    self.last_foreign_source = Some(reply.source);
+                }
+
+                let route_change = matches!(
+                    (self.last_ttl, reply.ttl),
+                    (Some(prev), Some(ttl)) if prev != ttl
+                );
+                if route_change {
+                    info!(
+                        "TTL changed {:?} -> {:?} for {}, probable route change",
+                        self.last_ttl, reply.ttl, self.target_addr
+                    );
+                    self.route_changes += 1;
+                }
+                if reply.ttl.is_some() {
+                    self.last_ttl = reply.ttl;
+                }
+                let size = (reply.size > 0).then_some(reply.size);
+                if size.is_some() {
+                    self.last_size = size;
+                }
+                self.meta.push_back(SampleMeta {
+                    ttl: reply.ttl,
+                    size,
+                    route_change,
+                });
             }
-            PingResult::Timeout => {
+            PingResult::Timeout { seq, sent } => {
                 debug!(
-                    "Processing Ping Timeout (Total failed: {})",
+                    "Processing Ping Timeout seq={} (Total failed: {})",
+                    seq,
                     self.failed_pings + 1
                 );
+                self.total_pings += 1;
                 self.failed_pings += 1;
+                self.outages.timeout(*sent);
                 self.history.push_back(None);
+                self.meta.push_back(SampleMeta::default());
             }
             PingResult::WebCheck { port, status } => {
                 debug!("Processing WebCheck Result: Port {} -> {:?}", port, status);
@@ -113,82 +319,101 @@ impl PingMonitor {
         }
 
         if self.history.len() > self.max_history {
-            self.history.pop_front();
+            self.drop_oldest();
+        }
+    }
+
+    fn drop_oldest(&mut self) {
+        if let Some(Some(_)) = self.history.pop_front() {
+            self.window.pop_oldest();
         }
+        self.meta.pop_front();
+    }
+
+    /// True when the probe in use is not the one that was configured.
+    pub fn probe_fell_back(&self) -> bool {
+        self.probe_method != self.requested_probe_method
     }
 
     pub fn latency_data(&self) -> &VecDeque<Option<f64>> {
         &self.history
     }
 
+    /// Every outage of the session, oldest first.
+    pub fn outage_list(&self) -> &[Outage] {
+        self.outages.outages()
+    }
+
+    /// Reply details aligned index-for-index with `latency_data()`.
+    pub fn sample_meta(&self) -> &VecDeque<SampleMeta> {
+        &self.meta
+    }
+
+    /// Snapshot of the current statistics. Runs in constant time regardless
+    /// of the history length; callers that draw every frame should still
+    /// cache the result once per tick.
     pub fn stats(&self) -> NetworkStats {
-        let valid: Vec<f64> = self.history.iter().filter_map(|&x| x).collect();
-        let recent_valid: Vec<f64> = self.recent.iter().copied().collect();
+        self.stats_at(Local::now())
+    }
 
+    /// Statistics with ongoing outages measured up to `now`.
+    pub fn stats_at(&self, now: DateTime<Local>) -> NetworkStats {
+        let window = &self.window;
         let current_response = self.history.back().and_then(|&x| x);
-        let current_avg = if !recent_valid.is_empty() {
-            recent_valid.iter().sum::<f64>() / recent_valid.len() as f64
+        let current_avg = if !self.recent.is_empty() {
+            self.recent.iter().sum::<f64>() / self.recent.len() as f64
         } else {
             0.0
         };
 
-        let avg_response = if !valid.is_empty() {
-            valid.iter().sum::<f64>() / valid.len() as f64
-        } else {
-            0.0
+        let lifetime = SpanStats {
+            sent: self.total_pings,
+            received: self.successful_pings,
+            loss_pct: loss_pct(self.total_pings, self.successful_pings),
+            avg_ms: self.lifetime_rtt.mean(),
+            min_ms: self.lifetime_rtt.min,
+            max_ms: self.lifetime_rtt.max,
+            std_dev: self.lifetime_rtt.std_dev(),
         };
-
-        let min_response = if valid.is_empty() {
-            0.0
-        } else {
-            valid.iter().fold(f64::INFINITY, |a, &b| a.min(b))
+        let window_stats = SpanStats {
+            sent: self.history.len() as u64,
+            received: window.len() as u64,
+            loss_pct: loss_pct(self.history.len() as u64, window.len() as u64),
+            avg_ms: window.mean(),
+            min_ms: window.sorted.first().copied().unwrap_or(0.0),
+            max_ms: window.sorted.last().copied().unwrap_or(0.0),
+            std_dev: window.std_dev(),
         };
 
-        let max_response = if valid.is_empty() {
-            0.0_f64
-        } else {
-            valid.iter().fold(0.0_f64, |a, &b| a.max(b))
-        };
+        let mean_delta = window.mean_delta();
 
-        let uptime_pct = if self.total_pings > 0 {
-            (self.successful_pings as f64 / self.total_pings as f64) * 100.0
-        } else {
-            0.0
-        };
-
-        let packet_loss_pct = if self.total_pings > 0 {
-            (self.failed_pings as f64 / self.total_pings as f64) * 100.0
-        } else {
-            0.0
-        };
-
-        // Calculate jitter (standard deviation of latency)
-        let jitter = if valid.len() > 1 {
-            let mean = avg_response;
-            let variance =
-                valid.iter().map(|&x| (x - mean).powi(2)).sum::<f64>() / valid.len() as f64;
-            variance.sqrt()
-        } else {
-            0.0
+        let percentiles = Percentiles {
+            p50: percentile(&window.sorted, 50.0),
+            p90: percentile(&window.sorted, 90.0),
+            p95: percentile(&window.sorted, 95.0),
+            p99: percentile(&window.sorted, 99.0),
         };
 
-        let stability = if avg_response > 0.0 {
-            (100.0 - (jitter / avg_response * 100.0)).clamp(0.0, 100.0)
+        let stability = if window_stats.avg_ms > 0.0 {
+            (100.0 - (window_stats.std_dev / window_stats.avg_ms * 100.0)).clamp(0.0, 100.0)
         } else {
             100.0
         };
 
+        // Score the current state: recent average, smoothed jitter and loss in the window
+        let r_factor = quality::r_factor(current_avg, self.rfc_jitter, window_stats.loss_pct);
+        let mos = quality::mos(r_factor);
+
         let quality = if current_response.is_none() && self.history.back().is_some() {
-            // Only report OFFLINE if the MOST RECENT ping failed (history.back() is the latest pushed, which is None)
-            // Wait, history.back() returns Option<&Option<f64>>.
-            // If pushed None, back() is Some(None).
-            // So current_response is None.
+            // The most recent probe failed
             "OFFLINE".to_string()
-        } else if current_avg < 30.0 {
+        } else if window.len() == 0 {
+            "UNKNOWN".to_string()
+        } else if mos >= 4.3 {
             "EXCELLENT".to_string()
-        } else if current_avg < 100.0 {
+        } else if mos >= 4.0 {
             "GOOD".to_string()
-        } else if current_avg < 200.0 {
+        } else if mos >= 3.6 {
             "FAIR".to_string()
         } else {
             "POOR".to_string()
@@ -197,44 +422,487 @@ impl PingMonitor {
         NetworkStats {
             current_response,
             current_avg,
-            avg_response,
-            min_response,
-            max_response,
-            uptime_pct,
-            packet_loss_pct,
-            jitter,
+            lifetime,
+            window: window_stats,
+            jitter: self.rfc_jitter,
+            mean_delta,
+            percentiles,
+            r_factor,
+            mos,
             stability,
             quality,
-            total_pings: self.total_pings,
+            ttl: self.last_ttl,
+            reply_size: self.last_size,
+            route_changes: self.route_changes,
+            foreign_replies: self.foreign_replies,
+            last_foreign_source: self.last_foreign_source,
+            late_replies: self.late_replies,
+            duplicate_replies: self.duplicate_replies,
+            reordered_replies: self.reordered_replies,
+            outages: self.outages.summary(now),
             dns_duration: self.dns_duration,
+            path_mtu: self.path_mtu.clone(),
             tcp_port_80: self.tcp_80.clone(),
             tcp_port_443: self.tcp_443.clone(),
         }
     }
 
-    pub fn quality(&self) -> String {
-        self.stats().quality
+    /// RTT percentiles over every reply of the session, unlike
+    /// `NetworkStats::percentiles` which covers the graph window.
+    pub fn lifetime_percentiles(&self) -> Percentiles {
+        self.lifetime_rtt.percentiles()
+    }
+
+    /// Mean absolute difference between consecutive RTTs of the session.
+    pub fn lifetime_mean_delta(&self) -> f64 {
+        self.lifetime_rtt.mean_delta()
     }
 
     pub fn reset(&mut self) {
         self.history.clear();
+        self.window.clear();
+        self.meta.clear();
         self.recent.clear();
+        self.rfc_jitter = 0.0;
+        self.last_rtt = None;
         self.total_pings = 0;
         self.successful_pings = 0;
         self.failed_pings = 0;
+        self.lifetime_rtt = RttTotals::default();
+        self.last_ttl = None;
+        self.last_size = None;
+        self.route_changes = 0;
+        self.foreign_replies = 0;
+        self.last_foreign_source = None;
+        self.late_replies = 0;
+        self.duplicate_replies = 0;
+        self.reordered_replies = 0;
+        self.outages.clear();
     }
 }
 
+/// Successful RTTs of the graph window with aggregates maintained on every
+/// push/pop, so statistics never need a pass over the history. Keeping
+/// `sorted` ordered costs a binary search plus an O(n) shift of the vector
+/// per sample, a single memmove that is cheap at graph-window sizes.
+#[derive(Debug, Default)]
+struct RttWindow {
+    /// Samples in arrival order
+    samples: VecDeque<f64>,
+    /// The same samples, ascending, for min/max and percentiles
+    sorted: Vec<f64>,
+    sum: f64,
+    sum_sq: f64,
+    /// Sum of |x(i) - x(i-1)| over consecutive samples
+    delta_sum: f64,
+}
+
+impl RttWindow {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            sorted: Vec::with_capacity(capacity),
+            ..Self::default()
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn push(&mut self, ms: f64) {
+        if let Some(&prev) = self.samples.back() {
+            self.delta_sum += (ms - prev).abs();
+        }
+        self.samples.push_back(ms);
+        self.sum += ms;
+        self.sum_sq += ms * ms;
+        let pos = self.sorted.partition_point(|&x| x < ms);
+        self.sorted.insert(pos, ms);
+    }
+
+    fn pop_oldest(&mut self) {
+        let Some(ms) = self.samples.pop_front() else {
+            return;
+        };
+        if self.samples.is_empty() {
+            // Start from exact zeros again instead of accumulating rounding error
+            self.clear();
+            return;
+        }
+        if let Some(&next) = self.samples.front() {
+            self.delta_sum -= (next - ms).abs();
+        }
+        self.sum -= ms;
+        self.sum_sq -= ms * ms;
+        let pos = self.sorted.partition_point(|&x| x < ms);
+        self.sorted.remove(pos);
+    }
+
+    fn clear(&mut self) {
+        self.samples.clear();
+        self.sorted.clear();
+        self.sum = 0.0;
+        self.sum_sq = 0.0;
+        self.delta_sum = 0.0;
+    }
+
+    fn mean(&self) -> f64 {
+        if self.samples.is_empty() {
+            0.0
+        } else {
+            self.sum / self.len() as f64
+        }
+    }
+
+    /// Population standard deviation.
+    fn std_dev(&self) -> f64 {
+        std_dev(self.len(), self.sum, self.sum_sq)
+    }
+
+    /// Mean absolute difference between consecutive samples.
+    fn mean_delta(&self) -> f64 {
+        if self.len() < 2 {
+            0.0
+        } else {
+            self.delta_sum.max(0.0) / (self.len() - 1) as f64
+        }
+    }
+}
+
+/// Lower edge of the first lifetime histogram bucket (ms).
+const BUCKET_BASE_MS: f64 = 0.01;
+/// Ratio between the edges of neighbouring buckets, i.e. 1% resolution.
+const BUCKET_GROWTH: f64 = 1.01;
+
+/// Sum, min, max and a log-bucketed histogram over every reply of the
+/// session, so lifetime percentiles need neither the samples nor a sort.
+#[derive(Debug, Default)]
+struct RttTotals {
+    count: usize,
+    sum: f64,
+    sum_sq: f64,
+    min: f64,
+    max: f64,
+    /// Sum of |x(i) - x(i-1)| over consecutive replies
+    delta_sum: f64,
+    last: f64,
+    /// Reply counts per bucket; bucket 0 holds everything below `BUCKET_BASE_MS`
+    buckets: Vec<u64>,
+}
+
+impl RttTotals {
+    fn add(&mut self, ms: f64) {
+        if self.count == 0 {
+            self.min = ms;
+            self.max = ms;
+        } else {
+            self.min = self.min.min(ms);
+            self.max = self.max.max(ms);
+            self.delta_sum += (ms - self.last).abs();
+        }
+        self.last = ms;
+        self.count += 1;
+        self.sum += ms;
+        self.sum_sq += ms * ms;
+
+        let bucket = bucket(ms);
+        if bucket >= self.buckets.len() {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+    }
+
+    fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+
+    fn std_dev(&self) -> f64 {
+        std_dev(self.count, self.sum, self.sum_sq)
+    }
+
+    fn mean_delta(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.delta_sum / (self.count - 1) as f64
+        }
+    }
+
+    /// Nearest-rank percentile from the histogram, accurate to about 1%.
+    fn percentile(&self, pct: f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let rank = (pct / 100.0 * (self.count - 1) as f64).round() as u64;
+        let mut seen = 0;
+        for (bucket, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen > rank {
+                // Geometric middle of the bucket, kept within the observed range
+                let mid = match bucket {
+                    0 => BUCKET_BASE_MS,
+                    b => BUCKET_BASE_MS * BUCKET_GROWTH.powf(b as f64 - 0.5),
+                };
+                return mid.clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    fn percentiles(&self) -> Percentiles {
+        Percentiles {
+            p50: self.percentile(50.0),
+            p90: self.percentile(90.0),
+            p95: self.percentile(95.0),
+            p99: self.percentile(99.0),
+        }
+    }
+}
+
+/// Histogram bucket of `ms`: bucket `b > 0` covers
+/// `BUCKET_BASE_MS * BUCKET_GROWTH^(b-1) .. BUCKET_BASE_MS * BUCKET_GROWTH^b`.
+fn bucket(ms: f64) -> usize {
+    if ms < BUCKET_BASE_MS {
+        0
+    } else {
+        (ms / BUCKET_BASE_MS).log(BUCKET_GROWTH).floor() as usize + 1
+    }
+}
+
+/// Population standard deviation from running sums.
+fn std_dev(count: usize, sum: f64, sum_sq: f64) -> f64 {
+    if count < 2 {
+        return 0.0;
+    }
+    let mean = sum / count as f64;
+    (sum_sq / count as f64 - mean * mean).max(0.0).sqrt()
+}
+
+fn loss_pct(sent: u64, received: u64) -> f64 {
+    if sent == 0 {
+        0.0
+    } else {
+        sent.saturating_sub(received) as f64 / sent as f64 * 100.0
+    }
+}
+
+/// Linear-interpolated percentile of an ascending slice, 0.0 when empty.
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    match sorted {
+        [] => 0.0,
+        [only] => *only,
+        _ => {
+            let rank = pct / 100.0 * (sorted.len() - 1) as f64;
+            let lower = rank.floor() as usize;
+            let upper = rank.ceil() as usize;
+            sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+        }
+    }
+}
+
+/// Wall-clock time of a past `Instant`.
+fn wall_clock(at: Instant) -> DateTime<Local> {
+    Local::now() - at.elapsed()
+}
+
+/// Time a TCP handshake to `port`, classifying failures the same way for every caller.
+async fn tcp_connect_time(target: IpAddr, port: u16, limit: Duration) -> WebCheckStatus {
+    trace!("Starting TCP {} check for {}", port, target);
+    let start = std::time::Instant::now();
+    match tokio::time::timeout(limit, TcpStream::connect((target, port))).await {
+        Ok(Ok(_)) => {
+            let dur = start.elapsed().as_secs_f64() * 1000.0;
+            trace!("TCP {} success: {:.2}ms", port, dur);
+            WebCheckStatus::Success(dur)
+        }
+        Ok(Err(e)) => {
+            debug!("TCP {} error: {}", port, e);
+            match e.kind() {
+                io::ErrorKind::ConnectionRefused => WebCheckStatus::ConnectionRefused,
+                io::ErrorKind::TimedOut => WebCheckStatus::Timeout,
+                _ => WebCheckStatus::Error(e.to_string()),
+            }
+        }
+        Err(_) => {
+            debug!("TCP {} timeout", port);
+            WebCheckStatus::Timeout
+        }
+    }
+}
+
+/// The per-task probe implementation selected from a `ProbeMethod`.
+enum Prober {
+    Icmp {
+        socket: EchoSocket,
+        tracker: SeqTracker,
+    },
+    Tcp(u16),
+}
+
+impl Prober {
+    /// Open the requested probe. ICMP tries a raw socket, then an unprivileged
+    /// datagram socket, then a TCP probe, so monitoring starts without root where possible.
+    async fn new(method: ProbeMethod, addr: IpAddr) -> Result<(Self, ProbeMethod)> {
+        match method {
+            ProbeMethod::Icmp => {
+                if let Ok(prober) = Self::icmp(addr, Type::RAW).await {
+                    return Ok(prober);
+                }
+                match Self::icmp(addr, Type::DGRAM).await {
+                    Ok(prober) => Ok(prober),
+                    Err(e) => {
+                        warn!("No ICMP socket available ({}), falling back to TCP/{} probes. Run as Administrator / grant CAP_NET_RAW or widen net.ipv4.ping_group_range for ICMP.", e, FALLBACK_TCP_PORT);
+                        Ok((
+                            Prober::Tcp(FALLBACK_TCP_PORT),
+                            ProbeMethod::Tcp(FALLBACK_TCP_PORT),
+                        ))
+                    }
+                }
+            }
+            ProbeMethod::IcmpUnprivileged => Self::icmp(addr, Type::DGRAM).await,
+            ProbeMethod::Tcp(port) => Ok((Prober::Tcp(port), method)),
+        }
+    }
+
+    async fn icmp(addr: IpAddr, sock_type: Type) -> Result<(Self, ProbeMethod)> {
+        let socket = EchoSocket::open(addr, sock_type)?;
+        let method = if socket.sock_type() == Type::RAW {
+            ProbeMethod::Icmp
+        } else {
+            ProbeMethod::IcmpUnprivileged
+        };
+        info!("Using {} probes for {}", method, addr);
+        Ok((
+            Prober::Icmp {
+                socket,
+                tracker: SeqTracker::default(),
+            },
+            method,
+        ))
+    }
+
+    /// Send probe `seq`. TCP probes complete immediately and return their
+    /// result; ICMP replies arrive later through `recv`.
+    async fn send(&mut self, addr: IpAddr, seq: u16, options: &ProbeOptions) -> Option<PingResult> {
+        match self {
+            Prober::Icmp { socket, tracker } => {
+                trace!("Sending ICMP Echo Request (seq={}) to {}", seq, addr);
+                tracker.sent(seq, Instant::now());
+                // A failed send is left outstanding and reported as a timeout
+                if let Err(e) = socket.send(seq, &options.payload).await {
+                    debug!("ICMP send error (seq={}) to {}: {}", seq, addr, e);
+                }
+                None
+            }
+            Prober::Tcp(port) => {
+                let sent = Local::now();
+                let start = Instant::now();
+                let rtt_ms = match tcp_connect_time(addr, *port, options.timeout).await {
+                    WebCheckStatus::Success(ms) => ms,
+                    // A refused connection still means the host answered with a RST,
+                    // so its handshake time is a valid round trip.
+                    WebCheckStatus::ConnectionRefused => {
+                        trace!("TCP probe (seq={}) to {}:{} refused", seq, addr, port);
+                        start.elapsed().as_secs_f64() * 1000.0
+                    }
+                    _ => return Some(PingResult::Timeout { seq, sent }),
+                };
+                Some(PingResult::Success(ProbeReply {
+                    seq,
+                    sent,
+                    rtt_ms,
+                    ttl: None,
+                    size: 0,
+                    source: addr,
+                    kind: ReplyKind::OnTime,
+                }))
+            }
+        }
+    }
+
+    /// Wait for the next ICMP echo reply. Never resolves for TCP probes.
+    async fn recv(&self, buf: &mut [u8]) -> EchoReply {
+        let Prober::Icmp { socket, .. } = self else {
+            return std::future::pending().await;
+        };
+        loop {
+            match socket.recv(buf).await {
+                Ok(Some(reply)) if reply.kind == ResponseKind::EchoReply => return reply,
+                Ok(_) => {}
+                Err(e) => {
+                    debug!("ICMP receive error: {}", e);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            }
+        }
+    }
+
+    /// Match an echo reply against the outstanding probes.
+    fn classify(&mut self, reply: EchoReply, at: Instant) -> Option<PingResult> {
+        let Prober::Icmp { tracker, .. } = self else {
+            return None;
+        };
+        let Some((kind, rtt)) = tracker.reply(reply.seq, at) else {
+            debug!("Ignoring reply for unknown seq={}", reply.seq);
+            return None;
+        };
+        let rtt_ms = rtt.as_secs_f64() * 1000.0;
+        trace!(
+            "ICMP Echo Reply (seq={}) from {}: {:.2}ms {:?}",
+            reply.seq,
+            reply.source,
+            rtt_ms,
+            kind
+        );
+        Some(PingResult::Success(ProbeReply {
+            seq: reply.seq,
+            sent: wall_clock(at - rtt),
+            rtt_ms,
+            ttl: reply.ttl,
+            size: reply.size,
+            source: reply.source,
+            kind,
+        }))
+    }
+
+    /// Report ICMP probes that have gone unanswered for too long.
+    fn expire(&mut self, now: Instant, timeout: Duration) -> Vec<PingResult> {
+        match self {
+            Prober::Icmp { tracker, .. } => tracker
+                .expire(now, timeout)
+                .into_iter()
+                .map(|(seq, sent)| PingResult::Timeout {
+                    seq,
+                    sent: wall_clock(sent),
+                })
+                .collect(),
+            Prober::Tcp(_) => Vec::new(),
+        }
+    }
+}
+
+/// Handle to a running background probe task.
+pub struct PingTask {
+    pub target_addr: IpAddr,
+    pub cmd_tx: mpsc::Sender<PingCommand>,
+    pub res_rx: mpsc::Receiver<PingResult>,
+    pub dns_duration: Option<f64>,
+    /// The probe actually in use, which may differ from the requested one after fallback
+    pub probe_method: ProbeMethod,
+}
+
 // Background Task Logic
 pub async fn start_ping_task(
     target: &str,
     interval_ms: u64,
-) -> Result<(
-    IpAddr,
-    mpsc::Sender<PingCommand>,
-    mpsc::Receiver<PingResult>,
-    Option<f64>,
-)> {
+    method: ProbeMethod,
+    options: ProbeOptions,
+) -> Result<PingTask> {
     let start_dns = std::time::Instant::now();
     debug!("Resolving target: {}", target);
     let target_addr: IpAddr = if let Ok(addr) = target.parse() {
@@ -262,101 +930,46 @@ pub async fn start_ping_task(
     let (cmd_tx, mut cmd_rx) = mpsc::channel(1);
     let (res_tx, res_rx) = mpsc::channel(100);
 
-    let config = Config::default();
-    let client = Client::new(&config).map_err(|e| {
-        error!("Failed to create ICMP client: {}. This usually means you lack raw socket permissions (Administrator on Windows, CAP_NET_RAW on Linux).", e);
-        anyhow::anyhow!("Network permission error: {e}")
-    })?;
     let addr = target_addr;
+    let (mut prober, probe_method) = Prober::new(method, addr).await?;
 
     tokio::spawn(async move {
-        debug!("Starting background ping task for {}", addr);
-        let mut pinger = client.pinger(addr, PingIdentifier(rand::random())).await;
-        let mut seq = 0;
+        debug!(
+            "Starting background {} probe task for {}",
+            probe_method, addr
+        );
+        let mut seq: u16 = 0;
         let mut interval = tokio::time::interval(Duration::from_millis(interval_ms));
+        let mut sweep = tokio::time::interval(Duration::from_millis(100));
         let mut web_check_enabled = false;
+        let mut options = options;
+        let mut buf = vec![0u8; MAX_PACKET];
 
         loop {
+            let mut results = Vec::new();
             tokio::select! {
                 _ = interval.tick() => {
-                     let payload = [0; 8];
-                     seq += 1;
-                     trace!("Sending ICMP Echo Request (seq={}) to {}", seq, addr);
+                     seq = seq.wrapping_add(1);
 
                      // Spawn web checks if enabled (fire and forget)
                      if web_check_enabled {
-                        let tx80 = res_tx.clone();
-                        let tx443 = res_tx.clone();
-                        let target = addr;
-
-                        tokio::spawn(async move {
-                            trace!("Starting TCP 80 check for {}", target);
-                            let start = std::time::Instant::now();
-                            let status = match tokio::time::timeout(Duration::from_secs(2), TcpStream::connect((target, 80))).await {
-                                Ok(Ok(_)) => {
-                                    let dur = start.elapsed().as_secs_f64() * 1000.0;
-                                    trace!("TCP 80 success: {:.2}ms", dur);
-                                    WebCheckStatus::Success(dur)
-                                },
-                                Ok(Err(e)) => {
-                                    debug!("TCP 80 error: {}", e);
-                                    match e.kind() {
-                                        io::ErrorKind::ConnectionRefused => WebCheckStatus::ConnectionRefused,
-                                        io::ErrorKind::TimedOut => WebCheckStatus::Timeout,
-                                        _ => WebCheckStatus::Error(e.to_string()),
-                                    }
-                                },
-                                Err(_) => {
-                                    debug!("TCP 80 timeout");
-                                    WebCheckStatus::Timeout
-                                },
-                            };
-                            let _ = tx80.send(PingResult::WebCheck { port: 80, status }).await;
-                        });
-
-                        tokio::spawn(async move {
-                            trace!("Starting TCP 443 check for {}", target);
-                            let start = std::time::Instant::now();
-                            let status = match tokio::time::timeout(Duration::from_secs(2), TcpStream::connect((target, 443))).await {
-                                Ok(Ok(_)) => {
-                                    let dur = start.elapsed().as_secs_f64() * 1000.0;
-                                    trace!("TCP 443 success: {:.2}ms", dur);
-                                    WebCheckStatus::Success(dur)
-                                },
-                                Ok(Err(e)) => {
-                                    debug!("TCP 443 error: {}", e);
-                                    match e.kind() {
-                                        io::ErrorKind::ConnectionRefused => WebCheckStatus::ConnectionRefused,
-                                        io::ErrorKind::TimedOut => WebCheckStatus::Timeout,
-                                        _ => WebCheckStatus::Error(e.to_string()),
-                                    }
-                                },
-                                Err(_) => {
-                                    debug!("TCP 443 timeout");
-                                    WebCheckStatus::Timeout
-                                },
-                            };
-                            let _ = tx443.send(PingResult::WebCheck { port: 443, status }).await;
-                        });
+                        for port in [80, 443] {
+                            let tx = res_tx.clone();
+                            let limit = options.timeout;
+                            tokio::spawn(async move {
+                                let status = tcp_connect_time(addr, port, limit).await;
+                                let _ = tx.send(PingResult::WebCheck { port, status }).await;
+                            });
+                        }
                      }
 
-                     match pinger.ping(PingSequence(seq), &payload).await {
-                         Ok((_, duration)) => {
-                             let ms = duration.as_secs_f64() * 1000.0;
-                             trace!("ICMP Echo Reply (seq={}) from {}: {:.2}ms", seq, addr, ms);
-                             if let Err(e) = res_tx.send(PingResult::Success(ms)).await {
-                                 warn!("Failed to send ping result to channel: {}", e);
-                                 break;
-                             }
-                         }
-                         Err(e) => {
-                             debug!("ICMP Ping error (seq={}) to {}: {}", seq, addr, e);
-                             if let Err(e) = res_tx.send(PingResult::Timeout).await {
-                                 warn!("Failed to send ping timeout to channel: {}", e);
-                                 break;
-                             }
-                         }
-                     }
+                     results.extend(prober.send(addr, seq, &options).await);
+                }
+                reply = prober.recv(&mut buf) => {
+                    results.extend(prober.classify(reply, Instant::now()));
+                }
+                _ = sweep.tick() => {
+                    results = prober.expire(Instant::now(), options.timeout);
                 }
                 Some(cmd) = cmd_rx.recv() => {
                     match cmd {
@@ -368,6 +981,14 @@ pub async fn start_ping_task(
                             info!("Ping interval changed to {}ms", ms);
                             interval = tokio::time::interval(Duration::from_millis(ms));
                         }
+                        PingCommand::SetProbeOptions(new_options) => {
+                            info!(
+                                "Probe options changed: timeout {:?}, {} byte payload",
+                                new_options.timeout,
+                                new_options.payload.len()
+                            );
+                            options = new_options;
+                        }
                         PingCommand::Stop => {
                             info!("Stopping ping task for {}", addr);
                             break;
@@ -375,23 +996,267 @@ pub async fn start_ping_task(
                     }
                 }
             }
+
+            for result in results {
+                if let Err(e) = res_tx.send(result).await {
+                    warn!("Failed to send ping result to channel: {}", e);
+                    return;
+                }
+            }
         }
     });
 
-    Ok((target_addr, cmd_tx, res_rx, Some(dns_duration)))
+    Ok(PingTask {
+        target_addr,
+        cmd_tx,
+        res_rx,
+        dns_duration: Some(dns_duration),
+        probe_method,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeDelta;
     use tokio::time::timeout;
 
+    fn reply(seq: u16, ttl: u8, source: IpAddr) -> PingResult {
+        PingResult::Success(ProbeReply {
+            seq,
+            sent: Local::now(),
+            rtt_ms: 10.0,
+            ttl: Some(ttl),
+            size: 64,
+            source,
+            kind: ReplyKind::OnTime,
+        })
+    }
+
+    #[test]
+    fn test_percentiles_follow_window() {
+        let addr: IpAddr = "192.0.2.1".parse().unwrap();
+        let mut monitor = PingMonitor::new(addr, 101);
+        for ms in (1..=100).rev() {
+            let PingResult::Success(mut probe) = reply(0, 64, addr) else {
+                unreachable!()
+            };
+            probe.rtt_ms = ms as f64;
+            monitor.process_result(PingResult::Success(probe));
+        }
+        monitor.process_result(PingResult::Timeout {
+            seq: 0,
+            sent: Local::now(),
+        });
+
+        let p = monitor.stats().percentiles;
+        assert!((p.p50 - 50.5).abs() < 1e-9);
+        assert!((p.p90 - 90.1).abs() < 1e-9);
+        assert!((p.p99 - 99.01).abs() < 1e-9);
+
+        // Shrinking the window drops the oldest (slowest) samples
+        monitor.set_max_history(10);
+        let p = monitor.stats().percentiles;
+        assert!((p.p50 - 5.0).abs() < 1e-9);
+        let stats = monitor.stats();
+        assert_eq!(stats.window.max_ms, 9.0);
+        assert!((stats.window.loss_pct - 10.0).abs() < 1e-9);
+        // The lifetime block ignores the window size
+        assert_eq!(stats.lifetime.max_ms, 100.0);
+        assert_eq!(stats.lifetime.sent, 101);
+        assert!((stats.lifetime.avg_ms - 50.5).abs() < 1e-9);
+        assert!((monitor.lifetime_mean_delta() - 1.0).abs() < 1e-9);
+        let lifetime = monitor.lifetime_percentiles();
+        for (got, want) in [
+            (lifetime.p50, 50.5),
+            (lifetime.p90, 90.1),
+            (lifetime.p99, 99.01),
+        ] {
+            assert!((got - want).abs() / want < 0.015, "{got} vs {want}");
+        }
+    }
+
+    #[test]
+    fn test_jitter_measures_differences() {
+        let addr: IpAddr = "192.0.2.1".parse().unwrap();
+        let mut monitor = PingMonitor::new(addr, 100);
+        // Alternating 10/20 ms: large spread but a perfectly regular pattern
+        for i in 0..200 {
+            let PingResult::Success(mut probe) = reply(0, 64, addr) else {
+                unreachable!()
+            };
+            probe.rtt_ms = if i % 2 == 0 { 10.0 } else { 20.0 };
+            monitor.process_result(PingResult::Success(probe));
+        }
+
+        let stats = monitor.stats();
+        assert!((stats.window.std_dev - 5.0).abs() < 1e-9);
+        assert!((stats.mean_delta - 10.0).abs() < 1e-9);
+        // The RFC 3550 estimator converges on the mean difference
+        assert!((stats.jitter - 10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_window_aggregates_match_recomputation() {
+        let addr: IpAddr = "192.0.2.1".parse().unwrap();
+        let mut monitor = PingMonitor::new(addr, 50);
+        let mut rtts = Vec::new();
+        for i in 0..500u32 {
+            if i % 7 == 3 {
+                monitor.process_result(PingResult::Timeout {
+                    seq: 0,
+                    sent: Local::now(),
+                });
+                continue;
+            }
+            let ms = 5.0 + f64::from(i * 37 % 101) / 3.0;
+            rtts.push((i, ms));
+            let PingResult::Success(mut probe) = reply(0, 64, addr) else {
+                unreachable!()
+            };
+            probe.rtt_ms = ms;
+            monitor.process_result(PingResult::Success(probe));
+        }
+
+        // The last 50 probes, timeouts included
+        let valid: Vec<f64> = rtts
+            .iter()
+            .filter(|(i, _)| *i >= 450)
+            .map(|(_, ms)| *ms)
+            .collect();
+        let mean = valid.iter().sum::<f64>() / valid.len() as f64;
+        let variance = valid.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / valid.len() as f64;
+        let delta =
+            valid.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>() / (valid.len() - 1) as f64;
+
+        let stats = monitor.stats();
+        assert!((stats.window.avg_ms - mean).abs() < 1e-9);
+        assert!((stats.window.std_dev - variance.sqrt()).abs() < 1e-6);
+        assert!((stats.mean_delta - delta).abs() < 1e-9);
+        assert_eq!(
+            stats.window.min_ms,
+            valid.iter().copied().fold(f64::INFINITY, f64::min)
+        );
+        assert_eq!(
+            stats.window.max_ms,
+            valid.iter().copied().fold(0.0, f64::max)
+        );
+    }
+
+    #[test]
+    fn test_outages_use_send_times() {
+        let addr: IpAddr = "192.0.2.1".parse().unwrap();
+        let mut monitor = PingMonitor::new(addr, 100);
+        monitor.outages.threshold = 3;
+        let start = Local::now() - TimeDelta::seconds(60);
+        let at = |s: i64| start + TimeDelta::seconds(s);
+        for (seq, s) in [(1, 0), (2, 1), (3, 2)] {
+            monitor.process_result(PingResult::Timeout { seq, sent: at(s) });
+        }
+        let PingResult::Success(mut probe) = reply(4, 64, addr) else {
+            unreachable!()
+        };
+        probe.sent = at(3);
+        monitor.process_result(PingResult::Success(probe));
+
+        // Processed a minute later, but stamped when the probes went out
+        let outage = &monitor.outage_list()[0];
+        assert_eq!(outage.start, at(0));
+        assert_eq!(outage.end, Some(at(3)));
+    }
+
+    /// Timing check for `stats()`; run with
+    /// `cargo test --release -- --ignored bench_stats --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_stats_cost_is_flat() {
+        let addr: IpAddr = "192.0.2.1".parse().unwrap();
+        let mut per_call = Vec::new();
+        for history in [100, 1_000, 10_000, 100_000] {
+            let mut monitor = PingMonitor::new(addr, history);
+            for i in 0..history {
+                let PingResult::Success(mut probe) = reply(0, 64, addr) else {
+                    unreachable!()
+                };
+                probe.rtt_ms = 10.0 + (i % 17) as f64;
+                monitor.process_result(PingResult::Success(probe));
+            }
+
+            // Best of several rounds, so a busy machine doesn't skew one size
+            let calls = 20_000;
+            let ns = (0..5)
+                .map(|_| {
+                    let start = Instant::now();
+                    for _ in 0..calls {
+                        std::hint::black_box(monitor.stats());
+                    }
+                    start.elapsed().as_nanos() as f64 / calls as f64
+                })
+                .fold(f64::INFINITY, f64::min);
+            println!("history {history:>7}: {ns:>8.0} ns per stats()");
+            per_call.push(ns);
+        }
+        // 1000x more history must not make a snapshot noticeably slower
+        assert!(per_call[3] < per_call[0] * 3.0, "{per_call:?}");
+    }
+
+    #[test]
+    fn test_payload_pattern() {
+        assert_eq!(parse_payload_pattern("ff").unwrap(), vec![0xff]);
+        assert_eq!(
+            parse_payload_pattern("0xdeadbeef").unwrap(),
+            vec![0xde, 0xad, 0xbe, 0xef]
+        );
+        assert!(parse_payload_pattern("abc").is_err());
+        assert!(parse_payload_pattern("zz").is_err());
+
+        let options = ProbeOptions::new(1000, 5, &[0xaa, 0x55]);
+        assert_eq!(options.payload, vec![0xaa, 0x55, 0xaa, 0x55, 0xaa]);
+        assert_eq!(options.timeout, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_ttl_change_and_foreign_source() {
+        let target: IpAddr = "192.0.2.1".parse().unwrap();
+        let other: IpAddr = "198.51.100.7".parse().unwrap();
+        let mut monitor = PingMonitor::new(target, 60);
+
+        monitor.process_result(reply(1, 57, target));
+        monitor.process_result(PingResult::Timeout {
+            seq: 2,
+            sent: Local::now(),
+        });
+        monitor.process_result(reply(3, 57, target));
+        monitor.process_result(reply(4, 55, target));
+        monitor.process_result(reply(5, 55, other));
+
+        let stats = monitor.stats();
+        assert_eq!(stats.route_changes, 1);
+        assert_eq!(stats.ttl, Some(55));
+        assert_eq!(stats.reply_size, Some(64));
+        assert_eq!(stats.foreign_replies, 1);
+        assert_eq!(stats.last_foreign_source, Some(other));
+
+        let markers: Vec<bool> = monitor
+            .sample_meta()
+            .iter()
+            .map(|m| m.route_change)
+            .collect();
+        assert_eq!(markers, vec![false, false, false, true, false]);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_network_intelligence_flow() {
         // 1. Start the ping task against Google DNS
         // Note: this actually performs network IO, so it might flake if offline.
-        let (addr, cmd_tx, mut res_rx, dns_duration) = start_ping_task("8.8.8.8", 1000)
+        let PingTask {
+            target_addr: addr,
+            cmd_tx,
+            mut res_rx,
+            dns_duration,
+            ..
+        } = start_ping_task("8.8.8.8", 1000, ProbeMethod::Icmp, ProbeOptions::default())
             .await
             .expect("Failed to start ping task");
 
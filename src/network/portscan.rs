@@ -1,4 +1,5 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 use tokio::net::TcpStream;
@@ -12,7 +13,8 @@ pub struct PortResult {
     pub service: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PortStatus {
     Open,
     Closed,
@@ -20,16 +22,17 @@ pub enum PortStatus {
 }
 
 pub struct PortScanner {
-    _target: String,
+    target: String,
     target_ip: IpAddr,
     ports: Vec<u16>,
     results: Vec<PortResult>,
     current_index: usize,
     completed: bool,
+    timeout: Duration,
 }
 
 impl PortScanner {
-    pub async fn new(target: &str) -> Result<Self> {
+    pub async fn new(target: &str, timeout: Duration) -> Result<Self> {
         // Resolve target to IP
         debug!("Scanner resolving: {}", target);
         let target_ip: IpAddr = if let Ok(addr) = target.parse() {
@@ -77,15 +80,36 @@ impl PortScanner {
         ];
 
         Ok(Self {
-            _target: target.to_string(),
+            target: target.to_string(),
             target_ip,
             ports,
             results: Vec::new(),
             current_index: 0,
             completed: false,
+            timeout,
         })
     }
 
+    /// A completed scan with known results, e.g. one read back from a recording.
+    pub fn finished(target: &str, target_ip: IpAddr, results: &[(u16, PortStatus)]) -> Self {
+        Self {
+            target: target.to_string(),
+            target_ip,
+            ports: results.iter().map(|(port, _)| *port).collect(),
+            results: results
+                .iter()
+                .map(|(port, status)| PortResult {
+                    port: *port,
+                    status: status.clone(),
+                    service: Self::identify_service(*port),
+                })
+                .collect(),
+            current_index: results.len(),
+            completed: true,
+            timeout: Duration::ZERO,
+        }
+    }
+
     pub async fn update(&mut self) -> Result<bool> {
         if self.completed {
             return Ok(true);
@@ -121,7 +145,7 @@ impl PortScanner {
     async fn scan_port(&self, port: u16) -> PortStatus {
         let addr = SocketAddr::new(self.target_ip, port);
         trace!("Scanning port: {}", port);
-        match timeout(Duration::from_millis(1500), TcpStream::connect(addr)).await {
+        match timeout(self.timeout, TcpStream::connect(addr)).await {
             Ok(Ok(_)) => {
                 debug!("Port {} is OPEN", port);
                 PortStatus::Open
@@ -171,6 +195,14 @@ impl PortScanner {
         Some(service.to_string())
     }
 
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn target_ip(&self) -> IpAddr {
+        self.target_ip
+    }
+
     pub fn results(&self) -> &[PortResult] {
         &self.results
     }
@@ -0,0 +1,139 @@
+use super::NetworkStats;
+use std::fmt;
+
+/// R-factor of a perfect G.711 call before any delay or loss impairment.
+const R0: f64 = 93.2;
+
+/// Delay added by a typical de-jitter buffer and codec framing, in ms.
+const CODEC_DELAY_MS: f64 = 10.0;
+
+/// Packet-loss robustness of G.711 with packet loss concealment (G.113 Appendix I).
+const BPL: f64 = 25.1;
+
+/// Estimate the ITU-T G.107 E-model R-factor for a G.711 voice call.
+///
+/// One-way delay is taken as half the RTT plus a jitter buffer of twice the
+/// RFC 3550 jitter; loss is treated as random (BurstR = 1).
+pub fn r_factor(rtt_ms: f64, jitter_ms: f64, loss_pct: f64) -> f64 {
+    let delay = rtt_ms / 2.0 + 2.0 * jitter_ms + CODEC_DELAY_MS;
+    // Delay impairment Id, simplified from G.107 §B.3
+    let id = 0.024 * delay
+        + if delay > 177.3 {
+            0.11 * (delay - 177.3)
+        } else {
+            0.0
+        };
+    // Effective equipment impairment Ie-eff for Ie = 0
+    let loss = loss_pct.clamp(0.0, 100.0);
+    let ie_eff = 95.0 * loss / (loss + BPL);
+    (R0 - id - ie_eff).clamp(0.0, 100.0)
+}
+
+/// Convert an R-factor to an estimated Mean Opinion Score (G.107 Annex B).
+pub fn mos(r: f64) -> f64 {
+    if r <= 0.0 {
+        1.0
+    } else if r >= 100.0 {
+        4.5
+    } else {
+        1.0 + 0.035 * r + r * (r - 60.0) * (100.0 - r) * 7e-6
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UseCase {
+    Voip,
+    Video,
+    Gaming,
+}
+
+impl UseCase {
+    pub const ALL: [UseCase; 3] = [UseCase::Voip, UseCase::Video, UseCase::Gaming];
+}
+
+impl fmt::Display for UseCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UseCase::Voip => write!(f, "VoIP"),
+            UseCase::Video => write!(f, "Video"),
+            UseCase::Gaming => write!(f, "Gaming"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    Good,
+    Fair,
+    Poor,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Good => write!(f, "Good"),
+            Verdict::Fair => write!(f, "Fair"),
+            Verdict::Poor => write!(f, "Poor"),
+        }
+    }
+}
+
+/// Grade a value against "good" and "fair" upper limits.
+fn grade(value: f64, good: f64, fair: f64) -> Verdict {
+    if value <= good {
+        Verdict::Good
+    } else if value <= fair {
+        Verdict::Fair
+    } else {
+        Verdict::Poor
+    }
+}
+
+impl NetworkStats {
+    /// Whether the connection is fit for a use case right now.
+    pub fn verdict(&self, use_case: UseCase) -> Verdict {
+        if self.window.sent == 0 || self.current_response.is_none() {
+            return Verdict::Poor;
+        }
+        let rtt = self.current_avg;
+        let jitter = self.jitter;
+        let loss = self.window.loss_pct;
+        match use_case {
+            // G.107 user satisfaction bands: R >= 80 satisfied, >= 70 some users dissatisfied
+            UseCase::Voip if self.r_factor >= 80.0 => Verdict::Good,
+            UseCase::Voip if self.r_factor >= 70.0 => Verdict::Fair,
+            UseCase::Voip => Verdict::Poor,
+            // Video codecs tolerate more delay but stall on loss
+            UseCase::Video => grade(rtt, 150.0, 300.0)
+                .max(grade(jitter, 30.0, 50.0))
+                .max(grade(loss, 1.0, 3.0)),
+            // Fast-paced games notice every spike
+            UseCase::Gaming => grade(rtt, 50.0, 100.0)
+                .max(grade(jitter, 10.0, 25.0))
+                .max(grade(loss, 0.5, 2.0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_e_model() {
+        // Clean LAN: close to the G.711 maximum
+        let r = r_factor(1.0, 0.1, 0.0);
+        assert!(r > 92.0 && r <= R0);
+        assert!((mos(r) - 4.4).abs() < 0.05);
+
+        // Satellite-like delay alone pushes the call below "satisfied"
+        assert!(r_factor(600.0, 5.0, 0.0) < 70.0);
+
+        // 5% random loss costs about 16 R points with PLC
+        let lossy = r_factor(1.0, 0.1, 5.0);
+        assert!((r - lossy - 15.8).abs() < 0.5);
+
+        assert_eq!(mos(0.0), 1.0);
+        assert_eq!(mos(100.0), 4.5);
+    }
+}
@@ -52,6 +52,16 @@ impl SpeedTest {
         })
     }
 
+    /// A test that has already ended, e.g. one read back from a recording.
+    pub fn finished(state: SpeedTestState) -> Self {
+        Self {
+            state,
+            _target: String::new(),
+            tx: None,
+            rx: None,
+        }
+    }
+
     pub async fn update(&mut self) -> Result<bool> {
         // Initialize if in Preparing state
         if matches!(self.state, SpeedTestState::Preparing) {
@@ -150,7 +160,6 @@ impl SpeedTest {
         &self.state
     }
 
-    #[allow(dead_code)]
     pub fn is_complete(&self) -> bool {
         matches!(
             self.state,
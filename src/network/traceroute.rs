@@ -0,0 +1,314 @@
+use super::icmp::{EchoReply, EchoSocket, ResponseKind, MAX_PACKET};
+use anyhow::Result;
+use socket2::Type;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, info, trace, warn};
+
+/// Highest TTL probed before giving up on reaching the target.
+pub const MAX_HOPS: u8 = 30;
+
+/// Samples kept per hop for the sparkline.
+const HOP_HISTORY: usize = 60;
+
+/// Outcome of one TTL-limited probe.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HopResult {
+    Reply {
+        ttl: u8,
+        source: IpAddr,
+        rtt_ms: f64,
+        /// The target itself answered, so `ttl` is the path length
+        reached: bool,
+    },
+    Timeout {
+        ttl: u8,
+    },
+}
+
+/// Running statistics for a single hop.
+#[derive(Debug, Clone, Default)]
+pub struct HopStats {
+    /// Last router address seen at this TTL
+    pub addr: Option<IpAddr>,
+    pub sent: u64,
+    pub received: u64,
+    pub last_ms: Option<f64>,
+    pub best_ms: f64,
+    pub worst_ms: f64,
+    sum_ms: f64,
+    pub history: VecDeque<Option<f64>>,
+}
+
+impl HopStats {
+    fn record(&mut self, rtt_ms: Option<f64>) {
+        self.sent += 1;
+        self.last_ms = rtt_ms;
+        if let Some(ms) = rtt_ms {
+            if self.received == 0 {
+                self.best_ms = ms;
+                self.worst_ms = ms;
+            } else {
+                self.best_ms = self.best_ms.min(ms);
+                self.worst_ms = self.worst_ms.max(ms);
+            }
+            self.received += 1;
+            self.sum_ms += ms;
+        }
+        self.history.push_back(rtt_ms);
+        if self.history.len() > HOP_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn loss_pct(&self) -> f64 {
+        if self.sent == 0 {
+            0.0
+        } else {
+            (self.sent - self.received) as f64 / self.sent as f64 * 100.0
+        }
+    }
+
+    pub fn avg_ms(&self) -> Option<f64> {
+        (self.received > 0).then(|| self.sum_ms / self.received as f64)
+    }
+}
+
+/// MTR-style trace: discovers the path with TTL-limited pings, then keeps
+/// pinging every hop.
+pub struct Traceroute {
+    pub target: String,
+    pub target_addr: IpAddr,
+    /// Set when no raw ICMP socket could be opened
+    pub error: Option<String>,
+    hops: Vec<HopStats>,
+    reached_at: Option<u8>,
+    res_rx: Option<mpsc::Receiver<HopResult>>,
+}
+
+impl Traceroute {
+    /// Start tracing. Failures are kept in `error` so the panel can show them.
+    pub fn start(target: &str, target_addr: IpAddr, interval_ms: u64, timeout: Duration) -> Self {
+        let mut trace = Self::new(target, target_addr);
+        match start_trace_task(target_addr, interval_ms, timeout) {
+            Ok(rx) => trace.res_rx = Some(rx),
+            Err(e) => {
+                warn!("Traceroute to {} unavailable: {}", target_addr, e);
+                trace.error = Some(e.to_string());
+            }
+        }
+        trace
+    }
+
+    fn new(target: &str, target_addr: IpAddr) -> Self {
+        Self {
+            target: target.to_string(),
+            target_addr,
+            error: None,
+            hops: Vec::new(),
+            reached_at: None,
+            res_rx: None,
+        }
+    }
+
+    /// Drain results from the background task.
+    pub fn update(&mut self) {
+        let Some(rx) = self.res_rx.as_mut() else {
+            return;
+        };
+        let mut results = Vec::new();
+        while let Ok(result) = rx.try_recv() {
+            results.push(result);
+        }
+        for result in results {
+            self.process_result(result);
+        }
+    }
+
+    pub fn process_result(&mut self, result: HopResult) {
+        let ttl = match &result {
+            HopResult::Reply { ttl, .. } | HopResult::Timeout { ttl } => *ttl,
+        };
+        if self.reached_at.is_some_and(|reached| ttl > reached) {
+            return;
+        }
+        if self.hops.len() < ttl as usize {
+            self.hops.resize_with(ttl as usize, HopStats::default);
+        }
+        let hop = &mut self.hops[ttl as usize - 1];
+        match result {
+            HopResult::Reply {
+                source,
+                rtt_ms,
+                reached,
+                ..
+            } => {
+                hop.addr = Some(source);
+                hop.record(Some(rtt_ms));
+                if reached && self.reached_at.is_none_or(|r| ttl < r) {
+                    info!("Reached {} at hop {}", self.target_addr, ttl);
+                    self.reached_at = Some(ttl);
+                    self.hops.truncate(ttl as usize);
+                }
+            }
+            HopResult::Timeout { .. } => hop.record(None),
+        }
+    }
+
+    /// Hops up to the target, or up to the furthest hop that answered so far.
+    pub fn hops(&self) -> &[HopStats] {
+        let visible = match self.reached_at {
+            Some(ttl) => ttl as usize,
+            None => self
+                .hops
+                .iter()
+                .rposition(|h| h.received > 0)
+                .map_or(0, |i| i + 1),
+        };
+        &self.hops[..visible.min(self.hops.len())]
+    }
+
+    pub fn reached(&self) -> bool {
+        self.reached_at.is_some()
+    }
+}
+
+/// Open a raw ICMP socket and probe every TTL once per interval. The task
+/// stops when the receiver is dropped.
+fn start_trace_task(
+    addr: IpAddr,
+    interval_ms: u64,
+    timeout: Duration,
+) -> Result<mpsc::Receiver<HopResult>> {
+    // ICMP errors such as Time Exceeded are only delivered to raw sockets
+    let socket = EchoSocket::open(addr, Type::RAW).map_err(|e| {
+        anyhow::anyhow!("Traceroute needs a raw ICMP socket (run as Administrator / root or grant CAP_NET_RAW): {e}")
+    })?;
+    let (res_tx, res_rx) = mpsc::channel(256);
+
+    tokio::spawn(async move {
+        debug!("Starting traceroute task for {}", addr);
+        let mut outstanding: HashMap<u16, (u8, Instant)> = HashMap::new();
+        let mut max_ttl = MAX_HOPS;
+        let mut seq: u16 = 0;
+        let mut round = tokio::time::interval(Duration::from_millis(interval_ms));
+        let mut sweep = tokio::time::interval(Duration::from_millis(100));
+        let mut buf = vec![0u8; MAX_PACKET];
+
+        loop {
+            let mut results = Vec::new();
+            tokio::select! {
+                _ = round.tick() => {
+                    for ttl in 1..=max_ttl {
+                        seq = seq.wrapping_add(1);
+                        if let Err(e) = socket.set_ttl(ttl) {
+                            debug!("Failed to set TTL {}: {}", ttl, e);
+                            continue;
+                        }
+                        outstanding.insert(seq, (ttl, Instant::now()));
+                        if let Err(e) = socket.send(seq, &[0; 8]).await {
+                            trace!("Trace probe (ttl={}) not sent: {}", ttl, e);
+                        }
+                    }
+                }
+                reply = next_response(&socket, &mut buf) => {
+                    if let Some((ttl, sent)) = outstanding.remove(&reply.seq) {
+                        let reached = reply.kind == ResponseKind::EchoReply;
+                        if reached && ttl < max_ttl {
+                            max_ttl = ttl;
+                            outstanding.retain(|_, (t, _)| *t <= ttl);
+                        }
+                        results.push(HopResult::Reply {
+                            ttl,
+                            source: reply.source,
+                            rtt_ms: sent.elapsed().as_secs_f64() * 1000.0,
+                            reached,
+                        });
+                    }
+                }
+                _ = sweep.tick() => {
+                    let mut expired: Vec<(u16, u8)> = outstanding
+                        .iter()
+                        .filter(|(_, (_, sent))| sent.elapsed() >= timeout)
+                        .map(|(seq, (ttl, _))| (*seq, *ttl))
+                        .collect();
+                    expired.sort_by_key(|(_, ttl)| *ttl);
+                    for (seq, ttl) in expired {
+                        outstanding.remove(&seq);
+                        results.push(HopResult::Timeout { ttl });
+                    }
+                }
+            }
+
+            for result in results {
+                if res_tx.send(result).await.is_err() {
+                    debug!("Traceroute to {} closed", addr);
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(res_rx)
+}
+
+/// Wait for the next echo reply or ICMP error answering one of our probes.
+async fn next_response(socket: &EchoSocket, buf: &mut [u8]) -> EchoReply {
+    loop {
+        match socket.recv(buf).await {
+            Ok(Some(reply)) => return reply,
+            Ok(None) => {}
+            Err(e) => {
+                debug!("ICMP receive error: {}", e);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hops_stop_at_target() {
+        let router: IpAddr = "10.0.0.1".parse().unwrap();
+        let target: IpAddr = "192.0.2.1".parse().unwrap();
+        let mut trace = Traceroute::new("example", target);
+
+        for ttl in 1..=4 {
+            trace.process_result(HopResult::Timeout { ttl });
+        }
+        assert!(trace.hops().is_empty());
+
+        trace.process_result(HopResult::Reply {
+            ttl: 1,
+            source: router,
+            rtt_ms: 2.0,
+            reached: false,
+        });
+        trace.process_result(HopResult::Reply {
+            ttl: 3,
+            source: target,
+            rtt_ms: 10.0,
+            reached: true,
+        });
+        trace.process_result(HopResult::Reply {
+            ttl: 4,
+            source: target,
+            rtt_ms: 11.0,
+            reached: true,
+        });
+
+        let hops = trace.hops();
+        assert_eq!(hops.len(), 3);
+        assert_eq!(hops[0].addr, Some(router));
+        assert_eq!(hops[0].loss_pct(), 50.0);
+        assert_eq!(hops[0].avg_ms(), Some(2.0));
+        assert_eq!(hops[1].addr, None);
+        assert_eq!(hops[2].addr, Some(target));
+        assert!(trace.reached());
+    }
+}
@@ -0,0 +1,803 @@
+use crate::network::{
+    PingResult, PortResult, PortStatus, ProbeMethod, ReplyKind, SpeedTestState, WebCheckStatus,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, SecondsFormat, Utc};
+use clap::ValueEnum;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Layout of the `--log` file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Comma-separated values with a header row
+    #[default]
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LogFormat::Csv => write!(f, "csv"),
+            LogFormat::Jsonl => write!(f, "jsonl"),
+        }
+    }
+}
+
+/// When the `--log` file is closed and a fresh one started in its place.
+#[derive(Debug, Clone, Default)]
+pub struct LogRotation {
+    /// Rotate once the file reaches this many bytes
+    pub max_bytes: Option<u64>,
+    /// Rotate when the local date changes
+    pub daily: bool,
+    /// Rotated files to keep, 0 keeps them all
+    pub keep: usize,
+    /// Compress rotated files with gzip
+    pub gzip: bool,
+}
+
+/// Buffered lines are written out at least this often, so a crash (the
+/// release build aborts on panic) loses at most this much of the log.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+const CSV_HEADER: &str =
+    "timestamp,type,target,ip,probe,seq,rtt_ms,status,port,service,download_mbps,upload_mbps,error";
+
+/// One line of the probe log. In CSV every type shares one set of columns
+/// and the ones a type doesn't use stay empty.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Entry<'a> {
+    Ping {
+        timestamp: String,
+        target: &'a str,
+        ip: IpAddr,
+        probe: String,
+        seq: u16,
+        /// `null` when the probe timed out
+        rtt_ms: Option<f64>,
+        status: &'static str,
+    },
+    WebCheck {
+        timestamp: String,
+        target: &'a str,
+        ip: IpAddr,
+        port: u16,
+        rtt_ms: Option<f64>,
+        status: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'a str>,
+    },
+    PortScan {
+        timestamp: String,
+        target: &'a str,
+        ip: IpAddr,
+        port: u16,
+        status: &'static str,
+        service: Option<&'a str>,
+    },
+    Speedtest {
+        timestamp: String,
+        download_mbps: Option<f64>,
+        upload_mbps: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'a str>,
+    },
+}
+
+impl Entry<'_> {
+    /// Values in `CSV_HEADER` order.
+    fn csv_row(&self) -> [String; 13] {
+        let opt = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        let mut row: [String; 13] = Default::default();
+        match self {
+            Entry::Ping {
+                timestamp,
+                target,
+                ip,
+                probe,
+                seq,
+                rtt_ms,
+                status,
+            } => {
+                row[0] = timestamp.clone();
+                row[1] = "ping".to_string();
+                row[2] = target.to_string();
+                row[3] = ip.to_string();
+                row[4] = probe.clone();
+                row[5] = seq.to_string();
+                row[6] = opt(*rtt_ms);
+                row[7] = status.to_string();
+            }
+            Entry::WebCheck {
+                timestamp,
+                target,
+                ip,
+                port,
+                rtt_ms,
+                status,
+                error,
+            } => {
+                row[0] = timestamp.clone();
+                row[1] = "web_check".to_string();
+                row[2] = target.to_string();
+                row[3] = ip.to_string();
+                row[6] = opt(*rtt_ms);
+                row[7] = status.to_string();
+                row[8] = port.to_string();
+                row[12] = error.unwrap_or_default().to_string();
+            }
+            Entry::PortScan {
+                timestamp,
+                target,
+                ip,
+                port,
+                status,
+                service,
+            } => {
+                row[0] = timestamp.clone();
+                row[1] = "port_scan".to_string();
+                row[2] = target.to_string();
+                row[3] = ip.to_string();
+                row[7] = status.to_string();
+                row[8] = port.to_string();
+                row[9] = service.unwrap_or_default().to_string();
+            }
+            Entry::Speedtest {
+                timestamp,
+                download_mbps,
+                upload_mbps,
+                error,
+            } => {
+                row[0] = timestamp.clone();
+                row[1] = "speedtest".to_string();
+                row[7] = if error.is_some() { "error" } else { "complete" }.to_string();
+                row[10] = opt(*download_mbps);
+                row[11] = opt(*upload_mbps);
+                row[12] = error.unwrap_or_default().to_string();
+            }
+        }
+        row
+    }
+}
+
+/// Quote a CSV field if it needs it (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// RFC 3339 UTC with milliseconds, e.g. `2024-05-01T03:12:00.123Z`.
+fn timestamp() -> String {
+    timestamp_at(Local::now())
+}
+
+/// `timestamp()` of a past moment, such as when a probe was sent.
+fn timestamp_at(at: DateTime<Local>) -> String {
+    at.with_timezone(&Utc)
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Milliseconds rounded to whole microseconds, which is all a probe resolves.
+fn round_us(ms: f64) -> f64 {
+    (ms * 1000.0).round() / 1000.0
+}
+
+/// Short probe name for log consumers: `icmp`, `icmp_dgram` or `tcp:<port>`.
+pub fn probe_name(method: ProbeMethod) -> String {
+    match method {
+        ProbeMethod::Icmp => "icmp".to_string(),
+        ProbeMethod::IcmpUnprivileged => "icmp_dgram".to_string(),
+        ProbeMethod::Tcp(port) => format!("tcp:{port}"),
+    }
+}
+
+/// The `--log` writer: every probe, web check, port scan and speed test.
+pub struct ProbeLog {
+    path: PathBuf,
+    format: LogFormat,
+    rotation: LogRotation,
+    writer: BufWriter<File>,
+    /// Size of the current file including buffered lines
+    written: u64,
+    /// When the current file was started, which names it once rotated
+    opened: DateTime<Local>,
+    last_flush: Instant,
+    /// Compression and pruning of the last rotated file
+    housekeeping: Option<JoinHandle<()>>,
+}
+
+impl ProbeLog {
+    pub fn open(
+        path: impl Into<PathBuf>,
+        format: LogFormat,
+        rotation: LogRotation,
+    ) -> Result<Self> {
+        let path = path.into();
+        debug!(
+            "Initializing {:?} log writer at: {}",
+            format,
+            path.display()
+        );
+        move_aside_other_schema(&path, format)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Cannot open log file {}", path.display()))?;
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        let metadata = file.metadata()?;
+        let mut writer = BufWriter::new(file);
+        let mut written = metadata.len();
+        let mut opened = Local::now();
+        if written == 0 {
+            written += write_header(&mut writer, format)?;
+        } else if let Ok(modified) = metadata.modified() {
+            // An existing file belongs to the day it was last written
+            opened = modified.into();
+        }
+        let log = Self {
+            path,
+            format,
+            rotation,
+            writer,
+            written,
+            opened,
+            last_flush: Instant::now(),
+            housekeeping: None,
+        };
+        info!("{}", log.describe());
+        Ok(log)
+    }
+
+    /// Where and how the log is written, for the startup message.
+    pub fn describe(&self) -> String {
+        let mut rotation = Vec::new();
+        if self.rotation.daily {
+            rotation.push("daily".to_string());
+        }
+        if let Some(bytes) = self.rotation.max_bytes {
+            rotation.push(format!("at {}", format_size(bytes)));
+        }
+        let mut text = format!("Logging {} to {}", self.format, self.path.display());
+        if !rotation.is_empty() {
+            text.push_str(&format!(", rotating {}", rotation.join(" and ")));
+            if self.rotation.keep > 0 {
+                text.push_str(&format!(", keeping {}", self.rotation.keep));
+            }
+            if self.rotation.gzip {
+                text.push_str(", gzipped");
+            }
+        }
+        text
+    }
+
+    /// Flush buffered lines if a flush is due. Call regularly.
+    pub fn tick(&mut self) {
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    pub fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if let Err(e) = self.writer.flush() {
+            error!("Failed to flush log {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Log a probe result. Ping rows carry the time the probe was sent, so a
+    /// timeout lands where the lost probe went out, not a timeout later.
+    pub fn ping(&mut self, target: &str, ip: IpAddr, probe: ProbeMethod, result: &PingResult) {
+        let entry = match result {
+            PingResult::Success(reply) => Entry::Ping {
+                timestamp: timestamp_at(reply.sent),
+                target,
+                ip,
+                probe: probe_name(probe),
+                seq: reply.seq,
+                rtt_ms: Some(round_us(reply.rtt_ms)),
+                status: match reply.kind {
+                    ReplyKind::OnTime => "ok",
+                    ReplyKind::Reordered => "reordered",
+                    ReplyKind::Late => "late",
+                    ReplyKind::Duplicate => "duplicate",
+                },
+            },
+            PingResult::Timeout { seq, sent } => Entry::Ping {
+                timestamp: timestamp_at(*sent),
+                target,
+                ip,
+                probe: probe_name(probe),
+                seq: *seq,
+                rtt_ms: None,
+                status: "timeout",
+            },
+            PingResult::WebCheck { port, status } => {
+                let (rtt_ms, label, error) = match status {
+                    WebCheckStatus::Untested => return,
+                    WebCheckStatus::Success(ms) => (Some(round_us(*ms)), "ok", None),
+                    WebCheckStatus::Timeout => (None, "timeout", None),
+                    WebCheckStatus::ConnectionRefused => (None, "refused", None),
+                    WebCheckStatus::Error(e) => (None, "error", Some(e.as_str())),
+                };
+                Entry::WebCheck {
+                    timestamp: timestamp(),
+                    target,
+                    ip,
+                    port: *port,
+                    rtt_ms,
+                    status: label,
+                    error,
+                }
+            }
+        };
+        self.write(&entry);
+    }
+
+    pub fn portscan(&mut self, target: &str, ip: IpAddr, results: &[PortResult]) {
+        let timestamp = timestamp();
+        for result in results {
+            self.write(&Entry::PortScan {
+                timestamp: timestamp.clone(),
+                target,
+                ip,
+                port: result.port,
+                status: match result.status {
+                    PortStatus::Open => "open",
+                    PortStatus::Closed => "closed",
+                    PortStatus::Filtered => "filtered",
+                },
+                service: result.service.as_deref(),
+            });
+        }
+    }
+
+    pub fn speedtest(&mut self, state: &SpeedTestState) {
+        let entry = match state {
+            SpeedTestState::Complete {
+                download_mbps,
+                upload_mbps,
+                ..
+            } => Entry::Speedtest {
+                timestamp: timestamp(),
+                download_mbps: Some(*download_mbps),
+                upload_mbps: Some(*upload_mbps),
+                error: None,
+            },
+            SpeedTestState::Error(e) => Entry::Speedtest {
+                timestamp: timestamp(),
+                download_mbps: None,
+                upload_mbps: None,
+                error: Some(e),
+            },
+            _ => return,
+        };
+        self.write(&entry);
+    }
+
+    fn write(&mut self, entry: &Entry) {
+        if self.rotation_due() {
+            if let Err(e) = self.rotate() {
+                error!("Failed to rotate log {}: {}", self.path.display(), e);
+            }
+        }
+        let mut line = match self.format {
+            LogFormat::Csv => {
+                let row: Vec<String> = entry.csv_row().iter().map(|f| csv_field(f)).collect();
+                row.join(",")
+            }
+            LogFormat::Jsonl => match serde_json::to_string(entry) {
+                Ok(json) => json,
+                Err(e) => {
+                    error!("Failed to serialize log entry: {}", e);
+                    return;
+                }
+            },
+        };
+        line.push('\n');
+        match self.writer.write_all(line.as_bytes()) {
+            Ok(()) => self.written += line.len() as u64,
+            Err(e) => error!("Failed to write to log {}: {}", self.path.display(), e),
+        }
+        self.tick();
+    }
+
+    fn rotation_due(&self) -> bool {
+        let too_big = self
+            .rotation
+            .max_bytes
+            .is_some_and(|max| self.written >= max);
+        let new_day = self.rotation.daily && Local::now().date_naive() != self.opened.date_naive();
+        too_big || new_day
+    }
+
+    /// Move the current file aside under its start time and continue in a
+    /// fresh one at the same path.
+    fn rotate(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        let rotated = rotated_path(&self.path, self.opened);
+        fs::rename(&self.path, &rotated)?;
+        info!("Rotated log to {}", rotated.display());
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.written = write_header(&mut self.writer, self.format)?;
+        self.opened = Local::now();
+
+        let path = self.path.clone();
+        let rotation = self.rotation.clone();
+        // Compressing a large file would stall the UI, so it happens aside
+        self.finish_housekeeping();
+        self.housekeeping = Some(std::thread::spawn(move || {
+            if rotation.gzip {
+                if let Err(e) = gzip(&rotated) {
+                    error!("Failed to compress {}: {}", rotated.display(), e);
+                }
+            }
+            if rotation.keep > 0 {
+                prune(&path, rotation.keep);
+            }
+        }));
+        Ok(())
+    }
+
+    fn finish_housekeeping(&mut self) {
+        if let Some(handle) = self.housekeeping.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for ProbeLog {
+    fn drop(&mut self) {
+        self.flush();
+        self.finish_housekeeping();
+    }
+}
+
+/// Start a new file with the CSV header, returning the bytes written.
+fn write_header(writer: &mut impl Write, format: LogFormat) -> io::Result<u64> {
+    if format == LogFormat::Csv {
+        writeln!(writer, "{CSV_HEADER}")?;
+        Ok(CSV_HEADER.len() as u64 + 1)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Start time in rotated file names, which sorts chronologically.
+const STAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+const STAMP_FORMAT_LEN: usize = "2024-05-01_03-12-00".len();
+
+/// `<stem>.` and `.<ext>` around the timestamp of rotated files.
+fn rotated_affixes(path: &Path) -> (String, String) {
+    let stem = path
+        .file_stem()
+        .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+    let ext = path
+        .extension()
+        .map_or_else(String::new, |e| format!(".{}", e.to_string_lossy()));
+    (format!("{stem}."), ext)
+}
+
+/// Rename an existing log at `path` out of the way, as if it had been
+/// rotated, when its first line doesn't match `format`. Appending would mix
+/// two schemas in one file, e.g. after an upgrade changed the CSV columns.
+fn move_aside_other_schema(path: &Path, format: LogFormat) -> Result<()> {
+    let first = match File::open(path) {
+        Ok(file) => BufReader::new(file).lines().next().transpose()?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Cannot open log file {}", path.display()))
+        }
+    };
+    let matches = match (&first, format) {
+        (None, _) => true,
+        (Some(line), LogFormat::Csv) => line == CSV_HEADER,
+        (Some(line), LogFormat::Jsonl) => line.starts_with('{'),
+    };
+    if matches {
+        return Ok(());
+    }
+    let modified = fs::metadata(path)?.modified()?;
+    let aside = rotated_path(path, modified.into());
+    fs::rename(path, &aside)
+        .with_context(|| format!("Cannot move {} out of the way", path.display()))?;
+    warn!(
+        "{} was written in another format, moved it to {} and started a new {} log",
+        path.display(),
+        aside.display(),
+        format
+    );
+    Ok(())
+}
+
+/// `latency.csv` started at `at` becomes `latency.2024-05-01_03-12-00.csv`,
+/// with a counter if that name is already taken.
+fn rotated_path(path: &Path, at: DateTime<Local>) -> PathBuf {
+    let (prefix, ext) = rotated_affixes(path);
+    let stamp = at.format(STAMP_FORMAT);
+    let mut rotated = path.with_file_name(format!("{prefix}{stamp}{ext}"));
+    let mut n = 1;
+    while rotated.exists() || gz_path(&rotated).exists() {
+        rotated = path.with_file_name(format!("{prefix}{stamp}-{n}{ext}"));
+        n += 1;
+    }
+    rotated
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// Replace `path` with `path.gz`.
+fn gzip(path: &Path) -> io::Result<()> {
+    let target = gz_path(path);
+    let mut encoder = GzEncoder::new(File::create(&target)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)?;
+    debug!("Compressed {}", target.display());
+    Ok(())
+}
+
+/// Delete the oldest rotated files of `path` beyond `keep`.
+fn prune(path: &Path, keep: usize) {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (prefix, ext) = rotated_affixes(path);
+    let gz_ext = format!("{ext}.gz");
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut rotated: Vec<((String, u32), PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let stamp = name
+                .strip_prefix(&prefix)?
+                .strip_suffix(&gz_ext)
+                .or_else(|| name.strip_prefix(&prefix)?.strip_suffix(&ext))?;
+            // Only names we generated, never the live file itself
+            let (time, counter) = stamp.split_at(stamp.len().min(STAMP_FORMAT_LEN));
+            NaiveDateTime::parse_from_str(time, STAMP_FORMAT).ok()?;
+            let counter = match counter.strip_prefix('-') {
+                Some(n) => n.parse().ok()?,
+                None if counter.is_empty() => 0,
+                None => return None,
+            };
+            Some(((time.to_string(), counter), e.path()))
+        })
+        .collect();
+    // Oldest first
+    rotated.sort();
+    let excess = rotated.len().saturating_sub(keep);
+    for (_, old) in rotated.into_iter().take(excess) {
+        debug!("Removing old log {}", old.display());
+        if let Err(e) = fs::remove_file(&old) {
+            warn!("Failed to remove {}: {}", old.display(), e);
+        }
+    }
+}
+
+/// Bytes as a short human-readable size, e.g. `10 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [(u64, &str); 3] = [(1 << 30, "GB"), (1 << 20, "MB"), (1 << 10, "KB")];
+    for (size, unit) in UNITS {
+        if bytes >= size {
+            let value = bytes as f64 / size as f64;
+            return if value.fract() == 0.0 {
+                format!("{value} {unit}")
+            } else {
+                format!("{value:.1} {unit}")
+            };
+        }
+    }
+    format!("{bytes} bytes")
+}
+
+/// Parse sizes like `500K`, `10M`, `1G` or a plain number of bytes.
+pub fn parse_size(input: &str) -> Result<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+    let value: f64 = value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size: {input}"))?;
+    let multiplier = match unit.trim().to_ascii_uppercase().trim_end_matches('B') {
+        "" => 1u64,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => anyhow::bail!("Invalid size unit in {input} (use K, M or G)"),
+    };
+    Ok((value * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::ProbeReply;
+
+    fn read_log(format: LogFormat, results: &[PingResult]) -> String {
+        let path =
+            std::env::temp_dir().join(format!("rping-log-{}-{:?}.txt", std::process::id(), format));
+        let path = path.to_string_lossy().into_owned();
+        {
+            let mut log = ProbeLog::open(&path, format, LogRotation::default()).unwrap();
+            let ip: IpAddr = "192.0.2.1".parse().unwrap();
+            for result in results {
+                log.ping("example.com", ip, ProbeMethod::Tcp(443), result);
+            }
+            log.speedtest(&SpeedTestState::Error("no route, giving up".to_string()));
+        }
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        contents
+    }
+
+    fn results() -> Vec<PingResult> {
+        vec![
+            PingResult::Success(ProbeReply {
+                seq: 7,
+                sent: Local::now(),
+                rtt_ms: 12.5,
+                ttl: None,
+                size: 0,
+                source: "192.0.2.1".parse().unwrap(),
+                kind: ReplyKind::OnTime,
+            }),
+            PingResult::Timeout {
+                seq: 8,
+                sent: "2024-05-01T03:12:00.123Z"
+                    .parse::<DateTime<Utc>>()
+                    .unwrap()
+                    .into(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_jsonl_records() {
+        let contents = read_log(LogFormat::Jsonl, &results());
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["type"], "ping");
+        assert_eq!(lines[0]["ip"], "192.0.2.1");
+        assert_eq!(lines[0]["probe"], "tcp:443");
+        assert_eq!(lines[0]["rtt_ms"], 12.5);
+        assert!(lines[1]["rtt_ms"].is_null());
+        assert_eq!(lines[1]["status"], "timeout");
+        assert_eq!(lines[2]["type"], "speedtest");
+
+        // RFC 3339 UTC with milliseconds
+        let ts = lines[0]["timestamp"].as_str().unwrap();
+        assert_eq!(ts.len(), "2024-05-01T03:12:00.123Z".len());
+        assert!(ts.ends_with('Z'));
+        // Probe rows are stamped when the probe was sent
+        assert_eq!(lines[1]["timestamp"], "2024-05-01T03:12:00.123Z");
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let contents = read_log(LogFormat::Csv, &results());
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[1].ends_with(",ping,example.com,192.0.2.1,tcp:443,7,12.5,ok,,,,,"));
+        assert!(lines[2].contains(",tcp:443,8,,timeout,"));
+        assert!(lines[3].ends_with(",,\"no route, giving up\""));
+    }
+
+    #[test]
+    fn test_rotation_by_size() {
+        let dir = std::env::temp_dir().join(format!("rping-rotate-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("latency.csv");
+        let rotation = LogRotation {
+            max_bytes: Some(300),
+            daily: false,
+            keep: 2,
+            gzip: true,
+        };
+        {
+            let mut log = ProbeLog::open(&path, LogFormat::Csv, rotation).unwrap();
+            let ip: IpAddr = "192.0.2.1".parse().unwrap();
+            for seq in 0..20 {
+                log.ping(
+                    "example.com",
+                    ip,
+                    ProbeMethod::Icmp,
+                    &PingResult::Timeout {
+                        seq,
+                        sent: Local::now(),
+                    },
+                );
+            }
+        }
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        // The live file plus the two newest rotated ones, compressed
+        assert_eq!(names.len(), 3, "{names:?}");
+        assert_eq!(names[2], "latency.csv");
+        assert!(names[..2]
+            .iter()
+            .all(|n| n.starts_with("latency.") && n.ends_with(".csv.gz")));
+
+        let mut rotated = String::new();
+        let gz = File::open(dir.join(&names[0])).unwrap();
+        io::Read::read_to_string(&mut flate2::read::GzDecoder::new(gz), &mut rotated).unwrap();
+        assert!(rotated.starts_with(CSV_HEADER));
+        let live = fs::read_to_string(&path).unwrap();
+        assert!(live.starts_with(CSV_HEADER));
+        assert!(live.len() <= 300 + 100);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_old_schema_is_moved_aside() {
+        let dir = std::env::temp_dir().join(format!("rping-schema-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("latency.csv");
+        let old = "Timestamp,Target,Latency(ms),Status\n2024-05-01 03:12:00,example.com,12.3,OK\n";
+        fs::write(&path, old).unwrap();
+
+        drop(ProbeLog::open(&path, LogFormat::Csv, LogRotation::default()).unwrap());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{CSV_HEADER}\n")
+        );
+        let aside: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| *p != path)
+            .collect();
+        assert_eq!(aside.len(), 1);
+        assert_eq!(fs::read_to_string(&aside[0]).unwrap(), old);
+
+        // A file in the current format is appended to
+        drop(ProbeLog::open(&path, LogFormat::Csv, LogRotation::default()).unwrap());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("10M").unwrap(), 10 << 20);
+        assert_eq!(parse_size("1.5kb").unwrap(), 1536);
+        assert!(parse_size("10X").is_err());
+    }
+}
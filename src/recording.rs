@@ -0,0 +1,583 @@
+use crate::network::{
+    PingResult, PortResult, PortStatus, ProbeMethod, ProbeReply, ReplyKind, SpeedTestState,
+    WebCheckStatus,
+};
+use crate::storage::{self, Config};
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeDelta};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// One line of a session recording. Every record after the header carries
+/// `t`, the milliseconds since the session started; ping records refer to
+/// their target by the `id` given in its `target` record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "k", rename_all = "snake_case")]
+pub enum Record {
+    /// First line of every recording
+    Session {
+        version: String,
+        started: DateTime<Local>,
+        interval_ms: u64,
+        history_length: usize,
+    },
+    Target {
+        t: u64,
+        id: usize,
+        target: String,
+        addr: IpAddr,
+        probe: ProbeMethod,
+    },
+    Reply {
+        t: u64,
+        id: usize,
+        seq: u16,
+        rtt: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ttl: Option<u8>,
+        size: usize,
+        src: IpAddr,
+        #[serde(default, skip_serializing_if = "is_on_time")]
+        kind: ReplyKind,
+        /// When the probe was sent, in the same units as `t`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sent: Option<u64>,
+    },
+    Timeout {
+        t: u64,
+        id: usize,
+        seq: u16,
+        /// When the probe was sent, in the same units as `t`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sent: Option<u64>,
+    },
+    WebCheck {
+        t: u64,
+        id: usize,
+        port: u16,
+        status: WebCheckStatus,
+    },
+    /// A finished speed test; `error` is set when it failed
+    SpeedTest {
+        t: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        download_mbps: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        upload_mbps: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        peak_mbps: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        secs: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// A finished port scan
+    PortScan {
+        t: u64,
+        target: String,
+        ports: Vec<(u16, PortStatus)>,
+    },
+    /// Statistics were reset by the user
+    Reset { t: u64 },
+}
+
+impl Record {
+    /// Milliseconds since the session started; 0 for the header.
+    pub fn t(&self) -> u64 {
+        match self {
+            Record::Session { .. } => 0,
+            Record::Target { t, .. }
+            | Record::Reply { t, .. }
+            | Record::Timeout { t, .. }
+            | Record::WebCheck { t, .. }
+            | Record::SpeedTest { t, .. }
+            | Record::PortScan { t, .. }
+            | Record::Reset { t } => *t,
+        }
+    }
+
+    /// The probe result of a ping record, with the id of its target, for a
+    /// recording that started at `started`.
+    pub fn ping_result(&self, started: DateTime<Local>) -> Option<(usize, PingResult)> {
+        let at = |ms: u64| started + TimeDelta::milliseconds(ms as i64);
+        let result = match self {
+            Record::Reply {
+                t,
+                id,
+                seq,
+                rtt,
+                ttl,
+                size,
+                src,
+                kind,
+                sent,
+            } => (
+                *id,
+                PingResult::Success(ProbeReply {
+                    seq: *seq,
+                    // Recordings made before send times were kept
+                    sent: sent.map_or_else(
+                        || at(*t) - TimeDelta::microseconds((rtt * 1000.0) as i64),
+                        at,
+                    ),
+                    rtt_ms: *rtt,
+                    ttl: *ttl,
+                    size: *size,
+                    source: *src,
+                    kind: *kind,
+                }),
+            ),
+            Record::Timeout { t, id, seq, sent } => (
+                *id,
+                PingResult::Timeout {
+                    seq: *seq,
+                    // Recordings made before send times were kept
+                    sent: at(sent.unwrap_or(*t)),
+                },
+            ),
+            Record::WebCheck {
+                id, port, status, ..
+            } => (
+                *id,
+                PingResult::WebCheck {
+                    port: *port,
+                    status: status.clone(),
+                },
+            ),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// The final state of a speed test record.
+    pub fn speedtest_state(&self) -> Option<SpeedTestState> {
+        let Record::SpeedTest {
+            download_mbps,
+            upload_mbps,
+            peak_mbps,
+            secs,
+            error,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        if let Some(error) = error {
+            return Some(SpeedTestState::Error(error.clone()));
+        }
+        let download_mbps = download_mbps.unwrap_or_default();
+        Some(SpeedTestState::Complete {
+            download_mbps,
+            upload_mbps: upload_mbps.unwrap_or_default(),
+            total_bytes: 0,
+            duration: Duration::from_secs_f64(secs.unwrap_or_default()),
+            avg_speed: download_mbps,
+            peak_speed: peak_mbps.unwrap_or(download_mbps),
+        })
+    }
+}
+
+fn is_on_time(kind: &ReplyKind) -> bool {
+    *kind == ReplyKind::OnTime
+}
+
+/// Writes a session recording as JSON Lines, one `Record` per line.
+pub struct Recorder {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    start: Instant,
+    /// Wall-clock time of `start`, as written to the header
+    started: DateTime<Local>,
+    /// Record id of each monitored target, indexed like the sessions
+    targets: Vec<usize>,
+    next_id: usize,
+    dirty: bool,
+}
+
+impl Recorder {
+    /// Start a new recording in the sessions directory, deleting the oldest
+    /// recordings beyond `config.max_recordings`.
+    pub fn create(config: &Config) -> Result<Self> {
+        let dir = sessions_dir()?;
+        prune(&dir, config.max_recordings.saturating_sub(1))?;
+        let stamp = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+        let mut n = 0;
+        loop {
+            // Another instance may have started a recording in the same second
+            let name = match n {
+                0 => format!("{stamp}.jsonl"),
+                n => format!("{stamp}-{n}.jsonl"),
+            };
+            match Self::create_at(dir.join(name), config) {
+                Err(e)
+                    if e.downcast_ref::<io::Error>()
+                        .is_some_and(|e| e.kind() == io::ErrorKind::AlreadyExists) =>
+                {
+                    n += 1
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Start a new recording at `path`, which must not exist yet.
+    pub fn create_at(path: PathBuf, config: &Config) -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        info!("Recording session to {}", path.display());
+        let mut recorder = Self {
+            path,
+            writer: Some(BufWriter::new(file)),
+            start: Instant::now(),
+            started: Local::now(),
+            targets: Vec::new(),
+            next_id: 0,
+            dirty: false,
+        };
+        recorder.write(&Record::Session {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started: recorder.started,
+            interval_ms: config.ping_interval_ms,
+            history_length: config.graph_history_length,
+        });
+        recorder.flush();
+        Ok(recorder)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    /// Milliseconds from the start of the recording to `at`.
+    fn since_start(&self, at: DateTime<Local>) -> u64 {
+        (at - self.started).num_milliseconds().max(0) as u64
+    }
+
+    /// Record a new target; it takes the next session index.
+    pub fn add_target(&mut self, target: &str, addr: IpAddr, probe: ProbeMethod) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.targets.push(id);
+        let record = Record::Target {
+            t: self.elapsed_ms(),
+            id,
+            target: target.to_string(),
+            addr,
+            probe,
+        };
+        self.write(&record);
+    }
+
+    /// The session at `index` stopped being monitored.
+    pub fn remove_target(&mut self, index: usize) {
+        if index < self.targets.len() {
+            self.targets.remove(index);
+        }
+    }
+
+    /// Record a result of the session at `index`.
+    pub fn ping(&mut self, index: usize, result: &PingResult) {
+        let Some(&id) = self.targets.get(index) else {
+            warn!("Not recording result for unknown target #{}", index);
+            return;
+        };
+        let t = self.elapsed_ms();
+        let record = match result {
+            PingResult::Success(reply) => Record::Reply {
+                t,
+                id,
+                seq: reply.seq,
+                // Microsecond resolution is plenty and keeps lines short
+                rtt: (reply.rtt_ms * 1000.0).round() / 1000.0,
+                ttl: reply.ttl,
+                size: reply.size,
+                src: reply.source,
+                kind: reply.kind,
+                sent: Some(self.since_start(reply.sent)),
+            },
+            PingResult::Timeout { seq, sent } => Record::Timeout {
+                t,
+                id,
+                seq: *seq,
+                sent: Some(self.since_start(*sent)),
+            },
+            PingResult::WebCheck { port, status } => Record::WebCheck {
+                t,
+                id,
+                port: *port,
+                status: status.clone(),
+            },
+        };
+        self.write(&record);
+    }
+
+    pub fn speedtest(&mut self, state: &SpeedTestState) {
+        let t = self.elapsed_ms();
+        let record = match state {
+            SpeedTestState::Complete {
+                download_mbps,
+                upload_mbps,
+                duration,
+                peak_speed,
+                ..
+            } => Record::SpeedTest {
+                t,
+                download_mbps: Some(*download_mbps),
+                upload_mbps: Some(*upload_mbps),
+                peak_mbps: Some(*peak_speed),
+                secs: Some(duration.as_secs_f64()),
+                error: None,
+            },
+            SpeedTestState::Error(e) => Record::SpeedTest {
+                t,
+                download_mbps: None,
+                upload_mbps: None,
+                peak_mbps: None,
+                secs: None,
+                error: Some(e.clone()),
+            },
+            _ => return,
+        };
+        self.write(&record);
+    }
+
+    pub fn portscan(&mut self, target: &str, results: &[PortResult]) {
+        let record = Record::PortScan {
+            t: self.elapsed_ms(),
+            target: target.to_string(),
+            ports: results.iter().map(|r| (r.port, r.status.clone())).collect(),
+        };
+        self.write(&record);
+    }
+
+    pub fn reset(&mut self) {
+        let record = Record::Reset {
+            t: self.elapsed_ms(),
+        };
+        self.write(&record);
+    }
+
+    fn write(&mut self, record: &Record) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        let result = serde_json::to_writer(&mut *writer, record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"));
+        if let Err(e) = result {
+            // Don't flood the log with one error per ping
+            error!("Stopped recording to {}: {}", self.path.display(), e);
+            self.writer = None;
+        } else {
+            self.dirty = true;
+        }
+    }
+
+    /// Push buffered records to disk so a crash loses at most one tick.
+    pub fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        if let Some(writer) = &mut self.writer {
+            if let Err(e) = writer.flush() {
+                error!("Failed to flush recording {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Where recordings are written.
+pub fn sessions_dir() -> Result<PathBuf> {
+    let dir = storage::config_dir()?.join("sessions");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Delete the oldest recordings so at most `keep` remain.
+fn prune(dir: &Path, keep: usize) -> Result<()> {
+    let mut recordings: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    if recordings.len() <= keep {
+        return Ok(());
+    }
+    recordings.sort_by_cached_key(|path| recording_order(path));
+    for path in &recordings[..recordings.len() - keep] {
+        debug!("Removing old recording {}", path.display());
+        if let Err(e) = fs::remove_file(path) {
+            warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Sort key putting recordings in the order they were started: the
+/// timestamp in the name, then the counter added for recordings started in
+/// the same second (`-` would sort `stamp-1` before `stamp`).
+fn recording_order(path: &Path) -> (String, u32) {
+    let stem = path
+        .file_stem()
+        .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+    let stamp_len = "2024-05-01_03-12-00".len();
+    if let Some(n) = stem
+        .get(stamp_len..)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .and_then(|n| n.parse().ok())
+    {
+        return (stem[..stamp_len].to_string(), n);
+    }
+    (stem, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_round_trip() {
+        let path = std::env::temp_dir().join(format!("rping-rec-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let addr: IpAddr = "192.0.2.1".parse().unwrap();
+        {
+            let mut recorder = Recorder::create_at(path.clone(), &Config::default()).unwrap();
+            // An existing recording is never overwritten
+            assert!(Recorder::create_at(path.clone(), &Config::default()).is_err());
+
+            // The same target twice gets two ids
+            recorder.add_target("example.com", addr, ProbeMethod::Icmp);
+            recorder.add_target("example.com", addr, ProbeMethod::Icmp);
+            recorder.ping(
+                1,
+                &PingResult::Success(ProbeReply {
+                    seq: 1,
+                    sent: recorder.started + TimeDelta::milliseconds(250),
+                    rtt_ms: 12.345_678,
+                    ttl: Some(57),
+                    size: 16,
+                    source: addr,
+                    kind: ReplyKind::OnTime,
+                }),
+            );
+            recorder.remove_target(0);
+            recorder.ping(
+                0,
+                &PingResult::Timeout {
+                    seq: 2,
+                    sent: Local::now(),
+                },
+            );
+            recorder.ping(
+                1,
+                &PingResult::Timeout {
+                    seq: 3,
+                    sent: Local::now(),
+                },
+            );
+        }
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let records: Vec<Record> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 5);
+        assert!(matches!(records[0], Record::Session { .. }));
+        assert!(matches!(records[1], Record::Target { id: 0, .. }));
+        assert!(matches!(records[2], Record::Target { id: 1, .. }));
+        assert!(matches!(
+            records[3],
+            Record::Reply { id: 1, seq: 1, rtt, ttl: Some(57), kind: ReplyKind::OnTime, sent: Some(250), .. } if rtt == 12.346
+        ));
+        // Replay gets the send time back, not the time the reply was processed
+        let Record::Session { started, .. } = records[0] else {
+            unreachable!()
+        };
+        let Some((1, PingResult::Success(reply))) = records[3].ping_result(started) else {
+            panic!("expected a reply for target 1");
+        };
+        assert_eq!(reply.sent, started + TimeDelta::milliseconds(250));
+        assert!(matches!(
+            records[4],
+            Record::Timeout {
+                id: 1,
+                seq: 2,
+                sent: Some(_),
+                ..
+            }
+        ));
+        // On-time replies leave the kind out
+        assert!(!contents.lines().nth(3).unwrap().contains("kind"));
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let dir = std::env::temp_dir().join(format!("rping-prune-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for day in 1..=4 {
+            fs::write(dir.join(format!("2024-01-0{day}_00-00-00.jsonl")), "").unwrap();
+        }
+        prune(&dir, 2).unwrap();
+        let mut left: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(
+            left,
+            ["2024-01-03_00-00-00.jsonl", "2024-01-04_00-00-00.jsonl"]
+        );
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_of_same_second() {
+        let dir = std::env::temp_dir().join(format!("rping-prune-same-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let names = [
+            "2024-05-01_03-11-59.jsonl",
+            "2024-05-01_03-12-00.jsonl",
+            "2024-05-01_03-12-00-1.jsonl",
+            "2024-05-01_03-12-00-2.jsonl",
+            "2024-05-01_03-12-00-10.jsonl",
+        ];
+        for name in names {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        prune(&dir, 2).unwrap();
+        let mut left: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(
+            left,
+            [
+                "2024-05-01_03-12-00-10.jsonl",
+                "2024-05-01_03-12-00-2.jsonl"
+            ]
+        );
+    }
+}
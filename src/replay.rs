@@ -0,0 +1,304 @@
+use crate::app::{App, TargetSession};
+use crate::network::{PortScanner, ProbeMethod, SpeedTest};
+use crate::recording::{sessions_dir, Record};
+use crate::storage::Config;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, TimeDelta};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Playback speeds offered by the replay keys.
+pub const SPEEDS: [u32; 3] = [1, 10, 100];
+
+/// A target as it appears in a recording.
+#[derive(Debug, Clone)]
+pub struct RecordedTarget {
+    pub target: String,
+    pub addr: IpAddr,
+    pub probe: ProbeMethod,
+}
+
+/// Plays a session recording back into an `App`, in place of the
+/// background ping tasks.
+pub struct Player {
+    file: String,
+    started: DateTime<Local>,
+    interval_ms: u64,
+    history_length: usize,
+    targets: Vec<RecordedTarget>,
+    /// Every record after the header, in recording order
+    records: Vec<Record>,
+    /// Index of the next record to apply
+    next: usize,
+    position: Duration,
+    length: Duration,
+    pub speed: u32,
+    pub paused: bool,
+    last_advance: Instant,
+}
+
+impl Player {
+    /// Load a recording. A bare file name is also looked up in the sessions
+    /// directory.
+    pub fn open(path: &Path) -> Result<Self> {
+        let path = resolve(path)?;
+        let reader = BufReader::new(
+            File::open(&path).with_context(|| format!("Cannot open {}", path.display()))?,
+        );
+        let mut lines = reader.lines().enumerate();
+
+        let header = lines
+            .next()
+            .map(|(_, line)| line)
+            .transpose()?
+            .and_then(|line| serde_json::from_str::<Record>(&line).ok());
+        let Some(Record::Session {
+            started,
+            interval_ms,
+            history_length,
+            ..
+        }) = header
+        else {
+            anyhow::bail!("{} is not a RustyPing session recording", path.display());
+        };
+
+        let mut records = Vec::new();
+        let mut targets = Vec::new();
+        for (number, line) in lines {
+            let line = line?;
+            match serde_json::from_str::<Record>(&line) {
+                Ok(Record::Target {
+                    target,
+                    addr,
+                    probe,
+                    ..
+                }) => targets.push(RecordedTarget {
+                    target,
+                    addr,
+                    probe,
+                }),
+                Ok(record) => records.push(record),
+                // A session that crashed can end in a partial line
+                Err(e) => warn!("Skipping line {} of {}: {}", number + 1, path.display(), e),
+            }
+        }
+        if targets.is_empty() {
+            anyhow::bail!("{} contains no targets", path.display());
+        }
+
+        let length = Duration::from_millis(records.last().map_or(0, Record::t));
+        info!(
+            "Loaded {} records over {:?} from {}",
+            records.len(),
+            length,
+            path.display()
+        );
+        Ok(Self {
+            file: path
+                .file_name()
+                .map_or_else(String::new, |n| n.to_string_lossy().into_owned()),
+            started,
+            interval_ms,
+            history_length,
+            targets,
+            records,
+            next: 0,
+            position: Duration::ZERO,
+            length,
+            speed: 1,
+            paused: false,
+            last_advance: Instant::now(),
+        })
+    }
+
+    /// Monitoring settings of the recorded session layered over `config`.
+    pub fn config(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+        config.ping_interval_ms = self.interval_ms;
+        config.graph_history_length = self.history_length;
+        // A replay must not notify anyone or record itself again
+        config.alerts.bell = false;
+        config.alerts.command = None;
+        config.alerts.webhook = None;
+        config.record_sessions = false;
+        config
+    }
+
+    /// Fresh, empty sessions for every recorded target.
+    pub fn sessions(&self, config: &Config) -> Vec<TargetSession> {
+        self.targets
+            .iter()
+            .map(|t| TargetSession::replay(t.target.clone(), t.addr, t.probe, config))
+            .collect()
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Wall-clock time of the playback position in the recorded session.
+    pub fn time(&self) -> DateTime<Local> {
+        self.started + TimeDelta::from_std(self.position).unwrap_or_default()
+    }
+
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    pub fn length(&self) -> Duration {
+        self.length
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.next >= self.records.len()
+    }
+
+    /// Move the playback position on by the real time since the last call,
+    /// scaled by the speed, and apply the records that became due.
+    pub fn advance(&mut self, app: &mut App) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_advance);
+        self.last_advance = now;
+        if self.paused {
+            return;
+        }
+        self.position = (self.position + elapsed * self.speed).min(self.length);
+        self.apply_due(app);
+        if self.at_end() && self.position >= self.length {
+            self.paused = true;
+        }
+    }
+
+    pub fn toggle_pause(&mut self, app: &mut App) {
+        if self.paused && self.at_end() {
+            // Start over once the end was reached
+            self.rewind(app);
+            self.position = Duration::ZERO;
+        }
+        self.paused = !self.paused;
+    }
+
+    /// Jump by `offset_secs` (negative to go back) and rebuild the state at
+    /// the new position.
+    pub fn seek(&mut self, app: &mut App, offset_secs: i64) {
+        let target = if offset_secs < 0 {
+            self.position
+                .saturating_sub(Duration::from_secs(offset_secs.unsigned_abs()))
+        } else {
+            self.position + Duration::from_secs(offset_secs as u64)
+        };
+        self.seek_to(app, target);
+    }
+
+    pub fn seek_to(&mut self, app: &mut App, position: Duration) {
+        let position = position.min(self.length);
+        if position < self.position {
+            debug!("Rewinding replay to {:?}", position);
+            self.rewind(app);
+        }
+        self.position = position;
+        self.apply_due(app);
+    }
+
+    /// Start from an empty state, as at the beginning of the recording.
+    fn rewind(&mut self, app: &mut App) {
+        app.targets = self.sessions(&app.config);
+        app.speedtest = None;
+        app.portscan = None;
+        self.next = 0;
+    }
+
+    fn apply_due(&mut self, app: &mut App) {
+        let position = self.position.as_millis() as u64;
+        while let Some(record) = self.records.get(self.next) {
+            if record.t() > position {
+                break;
+            }
+            self.next += 1;
+            if let Some((id, result)) = record.ping_result(self.started) {
+                if let Some(session) = app.targets.get_mut(id) {
+                    session.ping_monitor.process_result(result);
+                }
+            } else if let Some(state) = record.speedtest_state() {
+                app.speedtest = Some(SpeedTest::finished(state));
+            } else if let Record::PortScan { target, ports, .. } = record {
+                let addr = app
+                    .targets
+                    .iter()
+                    .find(|s| s.target == *target)
+                    .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |s| {
+                        s.ping_monitor.get_target_addr()
+                    });
+                app.portscan = Some(PortScanner::finished(target, addr, ports));
+            } else if let Record::Reset { .. } = record {
+                for session in &mut app.targets {
+                    session.ping_monitor.reset();
+                    session.alerts.clear();
+                }
+            }
+        }
+    }
+}
+
+/// Find a recording given as a path or as a file name in the sessions directory.
+fn resolve(path: &Path) -> Result<PathBuf> {
+    if path.exists() {
+        return Ok(path.to_path_buf());
+    }
+    let in_sessions = sessions_dir()?.join(path);
+    if in_sessions.exists() {
+        Ok(in_sessions)
+    } else {
+        anyhow::bail!("Recording {} not found", path.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seek_rebuilds_state() {
+        let path = std::env::temp_dir().join(format!("rping-replay-{}.jsonl", std::process::id()));
+        let mut lines = vec![
+            r#"{"k":"session","version":"2.7.0","started":"2024-05-01T03:12:00+00:00","interval_ms":1000,"history_length":100}"#.to_string(),
+            r#"{"k":"target","t":0,"id":0,"target":"example.com","addr":"192.0.2.1","probe":"Icmp"}"#.to_string(),
+        ];
+        for seq in 1..=10u64 {
+            let t = seq * 1000;
+            if seq % 5 == 0 {
+                lines.push(format!(r#"{{"k":"timeout","t":{t},"id":0,"seq":{seq}}}"#));
+            } else {
+                lines.push(format!(
+                    r#"{{"k":"reply","t":{t},"id":0,"seq":{seq},"rtt":{seq}.5,"size":16,"src":"192.0.2.1"}}"#
+                ));
+            }
+        }
+        // A crash can leave half a line behind
+        lines.push(r#"{"k":"reply","t":11000,"id"#.to_string());
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let player = Player::open(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(player.length(), Duration::from_secs(10));
+        let mut app = App::replay(player, &Config::default(), true);
+        assert_eq!(app.config.ping_interval_ms, 1000);
+
+        app.replay_seek_to(Duration::from_secs(7));
+        let stats = app.ping_monitor().stats();
+        assert_eq!((stats.lifetime.sent, stats.lifetime.received), (7, 6));
+        assert_eq!(stats.current_response, Some(7.5));
+        let expected: DateTime<Local> = "2024-05-01T03:12:07+00:00".parse().unwrap();
+        assert_eq!(app.now(), expected);
+
+        // Going back starts over from an empty state
+        app.replay_seek(-5);
+        let stats = app.ping_monitor().stats();
+        assert_eq!((stats.lifetime.sent, stats.lifetime.received), (2, 2));
+        assert_eq!(app.runtime(), Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,730 @@
+use crate::network::{NetworkStats, PingResult, ProbeMethod, ReplyKind, WebCheckStatus};
+use crate::probe_log::probe_name;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Lines queued between the UI and the sender task. When full, new lines
+/// are dropped rather than making the caller wait.
+const QUEUE: usize = 10_000;
+/// Lines kept for another attempt while the collector is unreachable.
+const MAX_BUFFERED: usize = 50_000;
+/// Lines per HTTP write, as InfluxDB recommends.
+const BATCH: usize = 5_000;
+/// How often the sender task ships what it has.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// Largest UDP payload sent, to stay clear of fragmentation.
+const MAX_DATAGRAM: usize = 1432;
+
+/// Where InfluxDB line protocol is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InfluxEndpoint {
+    /// `udp://host:port`, e.g. a Telegraf socket listener
+    Udp(String),
+    /// Full write URL, e.g. `http://localhost:8086/api/v2/write?org=o&bucket=b`
+    Http(String),
+}
+
+impl std::str::FromStr for InfluxEndpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(addr) = s.strip_prefix("udp://") {
+            Ok(Self::Udp(addr.trim_end_matches('/').to_string()))
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(Self::Http(s.to_string()))
+        } else {
+            Err(format!("'{s}' must start with udp://, http:// or https://"))
+        }
+    }
+}
+
+impl std::fmt::Display for InfluxEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Udp(addr) => write!(f, "udp://{addr}"),
+            Self::Http(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Influx,
+    Statsd,
+}
+
+enum Transport {
+    Udp(UdpSocket),
+    Http {
+        client: reqwest::Client,
+        url: String,
+        token: Option<String>,
+    },
+}
+
+impl Transport {
+    async fn udp(addr: &str) -> Result<Self> {
+        let target = tokio::net::lookup_host(addr)
+            .await
+            .with_context(|| format!("Cannot resolve {addr}"))?
+            .next()
+            .with_context(|| format!("No address for {addr}"))?;
+        let bind: SocketAddr = if target.is_ipv4() {
+            "0.0.0.0:0".parse()?
+        } else {
+            "[::]:0".parse()?
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(target).await?;
+        Ok(Self::Udp(socket))
+    }
+
+    /// Ship `lines`.
+    async fn send(&self, lines: &[String]) -> Result<(), SendError> {
+        match self {
+            Self::Udp(socket) => {
+                for datagram in datagrams(lines) {
+                    // Nobody listening is not worth a retry: UDP metrics are best effort
+                    if let Err(e) = socket.send(datagram.as_bytes()).await {
+                        debug!("UDP send failed: {}", e);
+                    }
+                }
+                Ok(())
+            }
+            Self::Http { client, url, token } => {
+                let mut request = client
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+                    .body(lines.join("\n"));
+                if let Some(token) = token {
+                    request =
+                        request.header(reqwest::header::AUTHORIZATION, format!("Token {token}"));
+                }
+                let response = request
+                    .send()
+                    .await
+                    .map_err(|e| SendError::Retry(e.into()))?;
+                let status = response.status();
+                if status.is_success() {
+                    return Ok(());
+                }
+                let body = response.text().await.unwrap_or_default();
+                let reason = format!(
+                    "{status}: {}",
+                    body.trim().chars().take(200).collect::<String>()
+                );
+                if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    Err(SendError::Rejected(status.as_u16(), reason))
+                } else {
+                    Err(SendError::Retry(anyhow::anyhow!(reason)))
+                }
+            }
+        }
+    }
+}
+
+/// Why a batch did not get through.
+enum SendError {
+    /// Transport errors, 429 and 5xx: the same batch may succeed later
+    Retry(anyhow::Error),
+    /// Any other 4xx, e.g. a malformed line or a bad token: resending the
+    /// batch would only be refused again
+    Rejected(u16, String),
+}
+
+/// Newline-separated lines packed into datagrams of at most `MAX_DATAGRAM`
+/// bytes. A longer line goes out on its own.
+fn datagrams(lines: &[String]) -> Vec<String> {
+    let mut datagrams = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + 1 + line.len() > MAX_DATAGRAM {
+            datagrams.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        datagrams.push(current);
+    }
+    datagrams
+}
+
+/// Pushes every probe result and periodic statistics to an InfluxDB or
+/// StatsD collector. Lines go through a bounded queue to a background task
+/// that batches them, so a slow or dead collector never holds up the caller.
+pub struct Sink {
+    protocol: Protocol,
+    endpoint: String,
+    tx: mpsc::Sender<String>,
+    task: JoinHandle<()>,
+    stats_interval: Duration,
+    last_stats: Instant,
+    /// Lines dropped since the queue last had room
+    dropped: u64,
+}
+
+impl Sink {
+    pub async fn influx(
+        endpoint: InfluxEndpoint,
+        token: Option<String>,
+        stats_interval: Duration,
+    ) -> Result<Self> {
+        let transport = match &endpoint {
+            InfluxEndpoint::Udp(addr) => Transport::udp(addr).await?,
+            InfluxEndpoint::Http(url) => Transport::Http {
+                client: reqwest::Client::builder()
+                    .timeout(Duration::from_secs(10))
+                    .build()?,
+                url: url.clone(),
+                token,
+            },
+        };
+        Ok(Self::start(
+            Protocol::Influx,
+            endpoint.to_string(),
+            transport,
+            stats_interval,
+        ))
+    }
+
+    pub async fn statsd(addr: &str, stats_interval: Duration) -> Result<Self> {
+        let transport = Transport::udp(addr).await?;
+        Ok(Self::start(
+            Protocol::Statsd,
+            format!("udp://{addr}"),
+            transport,
+            stats_interval,
+        ))
+    }
+
+    fn start(
+        protocol: Protocol,
+        endpoint: String,
+        transport: Transport,
+        stats_interval: Duration,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE);
+        info!("Pushing {:?} metrics to {}", protocol, endpoint);
+        let task = tokio::spawn(run(transport, rx));
+        Self {
+            protocol,
+            endpoint,
+            tx,
+            task,
+            stats_interval,
+            // The first aggregates go out one interval in, once there is something to say
+            last_stats: Instant::now(),
+            dropped: 0,
+        }
+    }
+
+    /// Where the sink pushes to, for the startup message.
+    pub fn describe(&self) -> String {
+        let name = match self.protocol {
+            Protocol::Influx => "InfluxDB line protocol",
+            Protocol::Statsd => "StatsD metrics",
+        };
+        format!(
+            "Pushing {name} to {} (statistics every {}s)",
+            self.endpoint,
+            self.stats_interval.as_secs_f64()
+        )
+    }
+
+    pub fn ping(&mut self, target: &str, ip: IpAddr, probe: ProbeMethod, result: &PingResult) {
+        let lines = match self.protocol {
+            Protocol::Influx => influx_ping(target, ip, probe, result),
+            Protocol::Statsd => statsd_ping(target, result),
+        };
+        for line in lines {
+            self.queue(line);
+        }
+    }
+
+    /// Aggregates for every target, sent once per statistics interval. The
+    /// iterator is only consumed when they are due.
+    pub fn stats<'a>(&mut self, targets: impl IntoIterator<Item = (&'a str, NetworkStats)>) {
+        if self.last_stats.elapsed() >= self.stats_interval {
+            self.stats_now(targets);
+        }
+    }
+
+    /// Aggregates for every target right away, e.g. at the end of a run.
+    pub fn stats_now<'a>(&mut self, targets: impl IntoIterator<Item = (&'a str, NetworkStats)>) {
+        self.last_stats = Instant::now();
+        for (target, stats) in targets {
+            let lines = match self.protocol {
+                Protocol::Influx => vec![influx_stats(target, &stats)],
+                Protocol::Statsd => statsd_stats(target, &stats),
+            };
+            for line in lines {
+                self.queue(line);
+            }
+        }
+    }
+
+    /// Ship what is still queued, giving up after a few seconds.
+    pub async fn close(self) {
+        drop(self.tx);
+        if tokio::time::timeout(Duration::from_secs(3), self.task)
+            .await
+            .is_err()
+        {
+            warn!("Gave up sending the last metrics to {}", self.endpoint);
+        }
+    }
+
+    fn queue(&mut self, line: String) {
+        match self.tx.try_send(line) {
+            Ok(()) => {
+                if self.dropped > 0 {
+                    info!(
+                        "{} is keeping up again after {} dropped lines",
+                        self.endpoint, self.dropped
+                    );
+                    self.dropped = 0;
+                }
+            }
+            Err(_) => {
+                if self.dropped == 0 {
+                    warn!("{} is not keeping up, dropping metrics", self.endpoint);
+                }
+                self.dropped += 1;
+            }
+        }
+    }
+}
+
+/// The sender task: collects lines and ships them in batches.
+async fn run(transport: Transport, mut rx: mpsc::Receiver<String>) {
+    let mut buffer: VecDeque<String> = VecDeque::new();
+    let mut flush =
+        tokio::time::interval_at(tokio::time::Instant::now() + FLUSH_INTERVAL, FLUSH_INTERVAL);
+    let mut open = true;
+    // Status of the last rejected batch, so a persistent error is logged once
+    let mut rejected = None;
+    while open || !buffer.is_empty() {
+        let full = tokio::select! {
+            line = rx.recv(), if open => match line {
+                Some(line) => {
+                    buffer.push_back(line);
+                    buffer.len() >= BATCH
+                }
+                // The sink was dropped: ship what is left and stop
+                None => {
+                    open = false;
+                    true
+                }
+            },
+            _ = flush.tick() => true,
+        };
+        if !full || buffer.is_empty() {
+            continue;
+        }
+        while !buffer.is_empty() {
+            let batch: Vec<String> = buffer.iter().take(BATCH).cloned().collect();
+            match transport.send(&batch).await {
+                Ok(()) => {
+                    buffer.drain(..batch.len());
+                    rejected = None;
+                }
+                Err(SendError::Rejected(status, reason)) => {
+                    if rejected != Some(status) {
+                        warn!(
+                            "Collector rejected {} metric lines, dropping them: {}",
+                            batch.len(),
+                            reason
+                        );
+                    } else {
+                        debug!(
+                            "Collector rejected {} metric lines: {}",
+                            batch.len(),
+                            reason
+                        );
+                    }
+                    rejected = Some(status);
+                    buffer.drain(..batch.len());
+                }
+                Err(SendError::Retry(e)) => {
+                    warn!("Metrics push failed, keeping {} lines: {}", buffer.len(), e);
+                    if buffer.len() > MAX_BUFFERED {
+                        let excess = buffer.len() - MAX_BUFFERED;
+                        warn!("Discarding the {} oldest metric lines", excess);
+                        buffer.drain(..excess);
+                    }
+                    if !open {
+                        // Shutting down, no point in waiting for the collector
+                        return;
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    debug!("Metrics sink stopped");
+}
+
+/// Escape a measurement name, tag key or tag value (commas, spaces, `=`).
+fn influx_key(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, ',' | ' ' | '=' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Quote a string field value.
+fn influx_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn influx_timestamp() -> i64 {
+    Utc::now().timestamp_nanos_opt().unwrap_or_default()
+}
+
+/// Nanosecond timestamp of a past moment, such as when a probe was sent.
+fn influx_timestamp_at(at: DateTime<Local>) -> i64 {
+    at.timestamp_nanos_opt().unwrap_or_default()
+}
+
+fn influx_ping(target: &str, ip: IpAddr, probe: ProbeMethod, result: &PingResult) -> Vec<String> {
+    let tags = format!(
+        "target={},ip={},probe={}",
+        influx_key(target),
+        ip,
+        influx_key(&probe_name(probe))
+    );
+    // Points are placed at the probe's send time, like the `--log` rows
+    let (fields, sent) = match result {
+        PingResult::Success(reply) => {
+            let status = match reply.kind {
+                ReplyKind::OnTime => "ok",
+                ReplyKind::Reordered => "reordered",
+                ReplyKind::Late => "late",
+                ReplyKind::Duplicate => "duplicate",
+            };
+            let mut fields = format!(
+                "seq={}i,status={},lost=false,rtt_ms={}",
+                reply.seq,
+                influx_string(status),
+                reply.rtt_ms
+            );
+            if let Some(ttl) = reply.ttl {
+                let _ = write!(fields, ",ttl={ttl}i");
+            }
+            (fields, reply.sent)
+        }
+        PingResult::Timeout { seq, sent } => (
+            format!("seq={seq}i,status={},lost=true", influx_string("timeout")),
+            *sent,
+        ),
+        PingResult::WebCheck { port, status } => {
+            let fields = match status {
+                WebCheckStatus::Untested => return Vec::new(),
+                WebCheckStatus::Success(ms) => format!("status=\"ok\",connect_ms={ms}"),
+                WebCheckStatus::Timeout => "status=\"timeout\"".to_string(),
+                WebCheckStatus::ConnectionRefused => "status=\"refused\"".to_string(),
+                WebCheckStatus::Error(e) => {
+                    format!("status=\"error\",error={}", influx_string(e))
+                }
+            };
+            return vec![format!(
+                "rping_web_check,{tags},port={port} {fields} {}",
+                influx_timestamp()
+            )];
+        }
+    };
+    vec![format!(
+        "rping_ping,{tags} {fields} {}",
+        influx_timestamp_at(sent)
+    )]
+}
+
+fn influx_stats(target: &str, stats: &NetworkStats) -> String {
+    let mut fields = format!(
+        "sent={}i,received={}i,loss_pct={},window_loss_pct={},avg_ms={},min_ms={},max_ms={},\
+         std_dev_ms={},jitter_ms={},p50_ms={},p90_ms={},p95_ms={},p99_ms={},mos={},outages={}i",
+        stats.lifetime.sent,
+        stats.lifetime.received,
+        stats.lifetime.loss_pct,
+        stats.window.loss_pct,
+        stats.window.avg_ms,
+        stats.window.min_ms,
+        stats.window.max_ms,
+        stats.window.std_dev,
+        stats.jitter,
+        stats.percentiles.p50,
+        stats.percentiles.p90,
+        stats.percentiles.p95,
+        stats.percentiles.p99,
+        stats.mos,
+        stats.outages.count,
+    );
+    if let Some(ms) = stats.current_response {
+        let _ = write!(fields, ",last_rtt_ms={ms}");
+    }
+    format!(
+        "rping_stats,target={} {fields} {}",
+        influx_key(target),
+        influx_timestamp()
+    )
+}
+
+/// `rping.<target>.` with the target reduced to characters StatsD keeps.
+fn statsd_prefix(target: &str) -> String {
+    let target: String = target
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("rping.{target}.")
+}
+
+fn statsd_ping(target: &str, result: &PingResult) -> Vec<String> {
+    let prefix = statsd_prefix(target);
+    match result {
+        PingResult::Success(reply) => match reply.kind {
+            ReplyKind::Late => vec![format!("{prefix}late:1|c")],
+            ReplyKind::Duplicate => vec![format!("{prefix}duplicate:1|c")],
+            ReplyKind::OnTime | ReplyKind::Reordered => vec![
+                format!("{prefix}sent:1|c"),
+                format!("{prefix}rtt:{}|ms", reply.rtt_ms),
+            ],
+        },
+        PingResult::Timeout { .. } => {
+            vec![format!("{prefix}sent:1|c"), format!("{prefix}lost:1|c")]
+        }
+        PingResult::WebCheck { port, status } => match status {
+            WebCheckStatus::Untested => Vec::new(),
+            WebCheckStatus::Success(ms) => vec![format!("{prefix}web_check.{port}:{ms}|ms")],
+            _ => vec![format!("{prefix}web_check.{port}.failed:1|c")],
+        },
+    }
+}
+
+fn statsd_stats(target: &str, stats: &NetworkStats) -> Vec<String> {
+    let prefix = statsd_prefix(target);
+    let up = if stats.current_response.is_some() {
+        1
+    } else {
+        0
+    };
+    vec![
+        format!("{prefix}up:{up}|g"),
+        format!("{prefix}loss_pct:{}|g", stats.window.loss_pct),
+        format!("{prefix}avg_ms:{}|g", stats.window.avg_ms),
+        format!("{prefix}jitter_ms:{}|g", stats.jitter),
+        format!("{prefix}p95_ms:{}|g", stats.percentiles.p95),
+        format!("{prefix}mos:{}|g", stats.mos),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::ProbeReply;
+    use chrono::Local;
+
+    fn reply(seq: u16, rtt_ms: f64) -> PingResult {
+        PingResult::Success(ProbeReply {
+            seq,
+            sent: Local::now(),
+            rtt_ms,
+            ttl: Some(57),
+            size: 64,
+            source: "192.0.2.1".parse().unwrap(),
+            kind: ReplyKind::OnTime,
+        })
+    }
+
+    async fn listener() -> (UdpSocket, String) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap().to_string();
+        (socket, addr)
+    }
+
+    async fn receive(socket: &UdpSocket) -> String {
+        let mut buf = [0u8; 2048];
+        let n = tokio::time::timeout(Duration::from_secs(5), socket.recv(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        String::from_utf8_lossy(&buf[..n]).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_statsd_over_udp() {
+        let (socket, addr) = listener().await;
+        let mut sink = Sink::statsd(&addr, Duration::ZERO).await.unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        sink.ping("1.1.1.1", ip, ProbeMethod::Icmp, &reply(1, 12.5));
+        sink.ping(
+            "1.1.1.1",
+            ip,
+            ProbeMethod::Icmp,
+            &PingResult::Timeout {
+                seq: 2,
+                sent: Local::now(),
+            },
+        );
+        sink.stats([("1.1.1.1", NetworkStats::default())]);
+
+        // Everything queued within a flush interval arrives as one datagram
+        let lines: Vec<String> = receive(&socket).await.lines().map(String::from).collect();
+        assert_eq!(
+            lines[..4],
+            [
+                "rping.1_1_1_1.sent:1|c",
+                "rping.1_1_1_1.rtt:12.5|ms",
+                "rping.1_1_1_1.sent:1|c",
+                "rping.1_1_1_1.lost:1|c",
+            ]
+        );
+        assert!(lines.contains(&"rping.1_1_1_1.up:0|g".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_influx_over_udp() {
+        let (socket, addr) = listener().await;
+        let endpoint: InfluxEndpoint = format!("udp://{addr}").parse().unwrap();
+        let mut sink = Sink::influx(endpoint, None, Duration::from_secs(3600))
+            .await
+            .unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let PingResult::Success(mut probe) = reply(7, 12.5) else {
+            unreachable!()
+        };
+        probe.sent = "2024-05-01T03:12:00.123Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .into();
+        sink.ping(
+            "my host",
+            ip,
+            ProbeMethod::Tcp(443),
+            &PingResult::Success(probe),
+        );
+        // Not due yet
+        sink.stats([("my host", NetworkStats::default())]);
+
+        let datagram = receive(&socket).await;
+        let (line, timestamp) = datagram.trim_end().rsplit_once(' ').unwrap();
+        assert_eq!(
+            line,
+            r#"rping_ping,target=my\ host,ip=192.0.2.1,probe=tcp:443 seq=7i,status="ok",lost=false,rtt_ms=12.5,ttl=57i"#
+        );
+        // Stamped with the send time, not when the reply was processed
+        assert_eq!(timestamp, "1714533120123000000");
+    }
+
+    #[tokio::test]
+    async fn test_dead_collector_never_blocks() {
+        // Accepts connections but never answers
+        let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/write", silent.local_addr().unwrap());
+        let mut sink = Sink::influx(InfluxEndpoint::Http(url), None, Duration::ZERO)
+            .await
+            .unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let start = Instant::now();
+        for seq in 0..(QUEUE as u16 * 2) {
+            sink.ping("example.com", ip, ProbeMethod::Icmp, &reply(seq, 1.0));
+        }
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(sink.dropped > 0);
+    }
+
+    /// An HTTP collector that answers with `statuses` in turn and hands
+    /// back the body of every write it receives.
+    async fn collector(statuses: &'static [u16]) -> (String, mpsc::Receiver<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/write", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            for status in statuses.iter().chain(std::iter::repeat(&204)) {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut data = Vec::new();
+                let mut buf = [0u8; 4096];
+                let body = loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    data.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&data).into_owned();
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                        continue;
+                    };
+                    let length = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break body.to_string();
+                    }
+                };
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Length: 2\r\nConnection: close\r\n\r\nno"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                let _ = tx.send(body).await;
+            }
+        });
+        (url, rx)
+    }
+
+    async fn next_write(writes: &mut mpsc::Receiver<String>) -> String {
+        tokio::time::timeout(Duration::from_secs(5), writes.recv())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rejected_batches_are_dropped() {
+        let (url, mut writes) = collector(&[400, 503]).await;
+        let mut sink = Sink::influx(InfluxEndpoint::Http(url), None, Duration::from_secs(3600))
+            .await
+            .unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+
+        // 400: the batch is bad and must not block the ones after it
+        sink.ping("example.com", ip, ProbeMethod::Icmp, &reply(1, 1.0));
+        assert!(next_write(&mut writes).await.contains("seq=1i"));
+        sink.ping("example.com", ip, ProbeMethod::Icmp, &reply(2, 1.0));
+        let write = next_write(&mut writes).await;
+        assert!(write.contains("seq=2i") && !write.contains("seq=1i"));
+        // 503: the collector is struggling, so the batch is sent again
+        let write = next_write(&mut writes).await;
+        assert!(write.contains("seq=2i") && !write.contains("seq=1i"));
+    }
+
+    #[test]
+    fn test_datagram_packing() {
+        let lines: Vec<String> = (0..100).map(|i| format!("{i:0>99}")).collect();
+        let datagrams = datagrams(&lines);
+        assert!(datagrams.iter().all(|d| d.len() <= MAX_DATAGRAM));
+        assert_eq!(datagrams.join("\n"), lines.join("\n"));
+    }
+}
@@ -1,8 +1,14 @@
+use crate::alerts::AlertConfig;
+use crate::network::{
+    parse_payload_pattern, Percentiles, ProbeMethod, ProbeOptions, DEFAULT_OUTAGE_THRESHOLD,
+};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
+use tracing::warn;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TargetEntry {
@@ -12,6 +18,8 @@ pub struct TargetEntry {
     pub total_sessions: u32,
     pub avg_latency: Option<f64>,
     pub success_rate: Option<f64>,
+    #[serde(default)]
+    pub percentiles: Option<Percentiles>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +30,70 @@ pub struct Config {
     pub show_jitter_panel: bool,
     pub show_history_panel: bool,
     pub speedtest_provider: String,
+    #[serde(default)]
+    pub probe_method: ProbeMethod,
+    #[serde(default = "default_probe_timeout_ms")]
+    pub probe_timeout_ms: u64,
+    #[serde(default = "default_payload_size")]
+    pub payload_size: usize,
+    /// Hex bytes repeated to fill the ICMP payload
+    #[serde(default = "default_payload_pattern")]
+    pub payload_pattern: String,
+    /// Consecutive timeouts that count as an outage
+    #[serde(default = "default_outage_threshold")]
+    pub outage_threshold: u32,
+    #[serde(default)]
+    pub alerts: AlertConfig,
+    /// Record every session to a file under the config directory
+    #[serde(default)]
+    pub record_sessions: bool,
+    /// Recordings kept before the oldest are deleted
+    #[serde(default = "default_max_recordings")]
+    pub max_recordings: usize,
+}
+
+fn default_probe_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_payload_size() -> usize {
+    8
+}
+
+fn default_payload_pattern() -> String {
+    "00".to_string()
+}
+
+fn default_outage_threshold() -> u32 {
+    DEFAULT_OUTAGE_THRESHOLD
+}
+
+fn default_max_recordings() -> usize {
+    50
+}
+
+/// Directory holding the history file and session recordings.
+pub fn config_dir() -> Result<PathBuf> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
+        .join("rustyping");
+
+    fs::create_dir_all(&config_dir)?;
+    Ok(config_dir)
+}
+
+impl Config {
+    pub fn probe_timeout(&self) -> Duration {
+        Duration::from_millis(self.probe_timeout_ms)
+    }
+
+    pub fn probe_options(&self) -> ProbeOptions {
+        let pattern = parse_payload_pattern(&self.payload_pattern).unwrap_or_else(|e| {
+            warn!("{}, using zeros", e);
+            vec![0]
+        });
+        ProbeOptions::new(self.probe_timeout_ms, self.payload_size, &pattern)
+    }
 }
 
 impl Default for Config {
@@ -33,6 +105,14 @@ impl Default for Config {
             show_jitter_panel: true,
             show_history_panel: true,
             speedtest_provider: "ookla".to_string(),
+            probe_method: ProbeMethod::Icmp,
+            probe_timeout_ms: default_probe_timeout_ms(),
+            payload_size: default_payload_size(),
+            payload_pattern: default_payload_pattern(),
+            outage_threshold: default_outage_threshold(),
+            alerts: AlertConfig::default(),
+            record_sessions: false,
+            max_recordings: default_max_recordings(),
         }
     }
 }
@@ -46,12 +126,7 @@ pub struct TargetHistory {
 
 impl TargetHistory {
     fn config_path() -> Result<PathBuf> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
-            .join("rustyping");
-
-        fs::create_dir_all(&config_dir)?;
-        Ok(config_dir.join("history.json"))
+        Ok(config_dir()?.join("history.json"))
     }
 
     pub fn load() -> Result<Self> {
@@ -90,10 +165,12 @@ impl TargetHistory {
                 total_sessions: 1,
                 avg_latency: None,
                 success_rate: None,
+                percentiles: None,
             });
         }
 
         // Sort by last used
+        #[allow(clippy::unnecessary_sort_by)]
         self.entries.sort_by(|a, b| b.last_used.cmp(&a.last_used));
     }
 
@@ -102,12 +179,15 @@ impl TargetHistory {
         self.entries.is_empty()
     }
 
-    pub fn print_recent(&self) {
+    pub fn print_recent(&self, limit: usize) {
         println!("\n┌─ Recent Targets ─────────────────────────┐");
-        for (i, entry) in self.entries.iter().take(10).enumerate() {
+        for (i, entry) in self.entries.iter().take(limit).enumerate() {
             let alias = entry.alias.as_deref().unwrap_or("");
             let stats = if let (Some(lat), Some(sr)) = (entry.avg_latency, entry.success_rate) {
-                format!(" ({lat:.1}ms, {sr:.1}%)")
+                match entry.percentiles {
+                    Some(p) => format!(" ({lat:.1}ms, p95 {:.1}ms, {sr:.1}%)", p.p95),
+                    None => format!(" ({lat:.1}ms, {sr:.1}%)"),
+                }
             } else {
                 String::new()
             };
@@ -122,10 +202,17 @@ impl TargetHistory {
         println!("└──────────────────────────────────────────┘\n");
     }
 
-    pub fn update_stats(&mut self, target: &str, avg_latency: f64, success_rate: f64) {
+    pub fn update_stats(
+        &mut self,
+        target: &str,
+        avg_latency: f64,
+        success_rate: f64,
+        percentiles: Percentiles,
+    ) {
         if let Some(entry) = self.entries.iter_mut().find(|e| e.target == target) {
             entry.avg_latency = Some(avg_latency);
             entry.success_rate = Some(success_rate);
+            entry.percentiles = Some(percentiles);
         }
     }
 }
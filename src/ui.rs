@@ -1,5 +1,9 @@
-use crate::app::App;
-use crate::network::{PortResult, PortStatus, SpeedTestState, WebCheckStatus};
+use crate::app::{App, AppView};
+use crate::network::{
+    format_duration, OutageSummary, PathMtu, PortResult, PortStatus, SpeedTestState, UseCase,
+    Verdict, WebCheckStatus,
+};
+use crate::replay::SPEEDS;
 use crate::theme::Theme;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
@@ -8,10 +12,11 @@ use ratatui::{
     text::{Line, Span},
     widgets::{
         canvas::{Canvas, Line as CanvasLine},
-        Block, Borders, Gauge, Paragraph,
+        Block, Borders, Cell, Clear, Gauge, Paragraph, Row, Table,
     },
     Frame,
 };
+use std::time::Duration;
 
 pub fn draw(f: &mut Frame, app: &mut App) {
     let area = f.area();
@@ -38,7 +43,7 @@ pub fn draw(f: &mut Frame, app: &mut App) {
 
     // Adjust layout based on panels and available height
     let stats_height = if area.height >= 35 {
-        13 // Full stats panel
+        14 // Full stats panel
     } else if area.height >= 28 {
         10 // Reduced stats
     } else {
@@ -86,16 +91,30 @@ pub fn draw(f: &mut Frame, app: &mut App) {
     if let Some(chunk) = chunks.first() {
         draw_header(f, app, *chunk);
     }
-    if let Some(chunk) = chunks.get(1) {
-        draw_latency_graph(f, app, *chunk);
-    }
 
     // Logic to handle variable chunk indices based on panels
     let stats_idx = 2;
     let panel_idx = if has_panels { 3 } else { 999 }; // 999 = invalid
     let footer_idx = if has_panels { 4 } else { 3 };
 
-    if let Some(chunk) = chunks.get(stats_idx) {
+    if app.view != AppView::Detail {
+        // Overview and traceroute replace both the graph and the statistics area
+        if let (Some(graph), Some(stats)) = (chunks.get(1), chunks.get(stats_idx)) {
+            if app.view == AppView::Trace {
+                draw_traceroute(f, app, graph.union(*stats));
+            } else {
+                draw_overview(f, app, graph.union(*stats));
+            }
+        }
+    } else if let Some(chunk) = chunks.get(1) {
+        draw_latency_graph(f, app, *chunk);
+    }
+
+    // In overview and traceroute mode the statistics area is already used by the table
+    if let Some(chunk) = chunks
+        .get(stats_idx)
+        .filter(|_| app.view == AppView::Detail)
+    {
         // Bottom section - responsive layout
         if app.show_jitter && area.width >= 100 {
             let bottom_chunks = Layout::default()
@@ -139,15 +158,18 @@ pub fn draw(f: &mut Frame, app: &mut App) {
     if app.show_diagnostics {
         draw_diagnostics_overlay(f, app);
     }
+    if app.show_add_target {
+        draw_add_target_overlay(f, app);
+    }
 }
 
 fn draw_header(f: &mut Frame, app: &App, area: Rect) {
-    let quality = app.ping_monitor.quality();
-    let status_color = app.theme.quality_color(&quality);
-    let stats = app.ping_monitor.stats();
+    let stats = app.stats();
+    let quality = &stats.quality;
+    let status_color = app.theme.quality_color(quality);
 
     // Enhanced header with more information
-    let ip_display = app.ping_monitor.get_target_addr().to_string();
+    let ip_display = app.ping_monitor().get_target_addr().to_string();
 
     let text = vec![Line::from(vec![
         Span::styled(
@@ -163,7 +185,16 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
         ),
         Span::raw("│"),
         Span::styled(
-            format!(" Target: {} ", &app.target),
+            if app.targets.len() > 1 {
+                format!(
+                    " Target: {} ({}/{}) ",
+                    app.target(),
+                    app.selected_target + 1,
+                    app.targets.len()
+                )
+            } else {
+                format!(" Target: {} ", app.target())
+            },
             Style::default().fg(app.theme.hi_fg),
         ),
         Span::raw("│"),
@@ -176,24 +207,68 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
         ),
         Span::raw(" │"),
         Span::styled(
-            format!(" Packets: {} ", stats.total_pings),
+            format!(" Packets: {} ", stats.lifetime.sent),
             Style::default().fg(app.theme.low),
         ),
     ])];
 
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(app.theme.box_color));
+
+    if let Some(player) = &app.replay {
+        block = block.title(
+            Line::from(Span::styled(
+                format!(
+                    " {} REPLAY {} ",
+                    if player.paused { "⏸" } else { "▶" },
+                    player.time().format("%Y-%m-%d %H:%M:%S")
+                ),
+                Style::default()
+                    .fg(app.theme.key_highlight)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .right_aligned(),
+        );
+    }
+
+    // Firing alerts take over the top border and flash twice a second
+    let firing: Vec<_> = app.firing_alerts().collect();
+    if let Some(latest) = firing.last() {
+        let more = if firing.len() > 1 {
+            format!(" (+{} more)", firing.len() - 1)
+        } else {
+            String::new()
+        };
+        let flash_on = (app.runtime().as_millis() / 500).is_multiple_of(2);
+        let style = if flash_on {
+            Style::default()
+                .bg(app.theme.crit)
+                .fg(app.theme.bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .fg(app.theme.crit)
+                .add_modifier(Modifier::BOLD)
+        };
+        block = block
+            .title(Span::styled(
+                format!(" ⚠ ALERT {}: {}{more} ", latest.target, latest.message),
+                style,
+            ))
+            .border_style(Style::default().fg(app.theme.crit));
+    }
+
     let header = Paragraph::new(text)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(app.theme.box_color)),
-        )
+        .block(block)
         .style(Style::default().bg(app.theme.bg).fg(app.theme.fg));
 
     f.render_widget(header, area);
 }
 
 fn draw_latency_graph(f: &mut Frame, app: &App, area: Rect) {
-    let data = app.ping_monitor.latency_data();
+    let data = app.ping_monitor().latency_data();
+    let meta = app.ping_monitor().sample_meta();
 
     // Enhanced empty state
     if data.is_empty() {
@@ -240,8 +315,11 @@ fn draw_latency_graph(f: &mut Frame, app: &App, area: Rect) {
     let time_window = format!("last {}s", data.len());
 
     // IP Display
-    let ip_display = app.ping_monitor.get_target_addr().to_string();
-    let title_text = format!(" {ip_display} │ {time_window} ");
+    let ip_display = app.ping_monitor().get_target_addr().to_string();
+    let title_text = format!(
+        " {ip_display} │ {} │ {time_window} ",
+        app.ping_monitor().probe_method
+    );
 
     // BRAILLE CANVAS - High-resolution rendering!
     // Right-to-Left Scrolling: Newest data is on the RIGHT side.
@@ -322,6 +400,17 @@ fn draw_latency_graph(f: &mut Frame, app: &App, area: Rect) {
                         color: app.theme.missed,
                     });
                 }
+
+                // Mark TTL changes (likely route changes) with a tick along the top edge
+                if meta.get(i).is_some_and(|m| m.route_change) {
+                    ctx.draw(&CanvasLine {
+                        x1: x_final,
+                        y1: y_max - (y_max - y_min) * 0.15,
+                        x2: x_final,
+                        y2: y_max,
+                        color: app.theme.key_highlight,
+                    });
+                }
             }
         });
 
@@ -329,7 +418,7 @@ fn draw_latency_graph(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn draw_statistics(f: &mut Frame, app: &App, area: Rect) {
-    let stats = app.ping_monitor.stats();
+    let stats = app.stats();
 
     let current_color = if let Some(rt) = stats.current_response {
         app.theme.latency_color(rt)
@@ -337,6 +426,14 @@ fn draw_statistics(f: &mut Frame, app: &App, area: Rect) {
         app.theme.crit
     };
 
+    let loss_color = |pct: f64| {
+        if pct > 0.0 {
+            app.theme.crit
+        } else {
+            app.theme.good
+        }
+    };
+
     // Enhanced statistics display with better formatting
     let text = vec![
         Line::from(vec![
@@ -355,9 +452,8 @@ fn draw_statistics(f: &mut Frame, app: &App, area: Rect) {
                 Style::default().fg(app.theme.latency_color(stats.current_avg)),
             ),
         ]),
-        Line::from(""),
         Line::from(vec![Span::styled(
-            "Session Statistics:",
+            format!("Window (last {}):", stats.window.sent),
             Style::default()
                 .fg(app.theme.title)
                 .add_modifier(Modifier::BOLD),
@@ -365,21 +461,19 @@ fn draw_statistics(f: &mut Frame, app: &App, area: Rect) {
         Line::from(vec![
             Span::styled("  Avg:    ", Style::default().fg(app.theme.low)),
             Span::styled(
-                format!("{:>8.1} ms", stats.avg_response),
+                format!("{:>8.1} ms", stats.window.avg_ms),
                 Style::default().fg(app.theme.fg),
             ),
         ]),
         Line::from(vec![
-            Span::styled("  Min:    ", Style::default().fg(app.theme.low)),
+            Span::styled("  Min/Max:", Style::default().fg(app.theme.low)),
             Span::styled(
-                format!("{:>8.1} ms", stats.min_response),
+                format!("{:>8.1}", stats.window.min_ms),
                 Style::default().fg(app.theme.good),
             ),
-        ]),
-        Line::from(vec![
-            Span::styled("  Max:    ", Style::default().fg(app.theme.low)),
+            Span::styled(" / ", Style::default().fg(app.theme.low)),
             Span::styled(
-                format!("{:>8.1} ms", stats.max_response),
+                format!("{:.1} ms", stats.window.max_ms),
                 Style::default().fg(app.theme.warn),
             ),
         ]),
@@ -390,21 +484,56 @@ fn draw_statistics(f: &mut Frame, app: &App, area: Rect) {
                 Style::default().fg(app.theme.fg),
             ),
         ]),
-        Line::from(""),
         Line::from(vec![
-            Span::styled("Uptime:    ", Style::default().fg(app.theme.good)),
+            Span::styled("  P50/P90:", Style::default().fg(app.theme.low)),
             Span::styled(
-                format!("{:>6.1}%", stats.uptime_pct),
+                format!(
+                    "{:>8.1} / {:.1} ms",
+                    stats.percentiles.p50, stats.percentiles.p90
+                ),
                 Style::default().fg(app.theme.fg),
             ),
         ]),
         Line::from(vec![
-            Span::styled("Loss:      ", Style::default().fg(app.theme.crit)),
+            Span::styled("  P95/P99:", Style::default().fg(app.theme.low)),
             Span::styled(
-                format!("{:>6.1}%", stats.packet_loss_pct),
+                format!(
+                    "{:>8.1} / {:.1} ms",
+                    stats.percentiles.p95, stats.percentiles.p99
+                ),
+                Style::default().fg(app.theme.latency_color(stats.percentiles.p99)),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  Loss:   ", Style::default().fg(app.theme.low)),
+            Span::styled(
+                format!("{:>8.1}%", stats.window.loss_pct),
+                Style::default().fg(loss_color(stats.window.loss_pct)),
+            ),
+        ]),
+        Line::from(vec![Span::styled(
+            format!("Session ({} probes):", stats.lifetime.sent),
+            Style::default()
+                .fg(app.theme.title)
+                .add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(vec![
+            Span::styled("  Avg:    ", Style::default().fg(app.theme.low)),
+            Span::styled(
+                format!("{:>8.1} ms", stats.lifetime.avg_ms),
                 Style::default().fg(app.theme.fg),
             ),
+            Span::styled("  Loss: ", Style::default().fg(app.theme.low)),
+            Span::styled(
+                format!("{:.1}%", stats.lifetime.loss_pct),
+                Style::default().fg(loss_color(stats.lifetime.loss_pct)),
+            ),
+            Span::styled(
+                format!(" (up {:.1}%)", stats.lifetime.uptime_pct()),
+                Style::default().fg(app.theme.low),
+            ),
         ]),
+        outage_line(app, &stats.outages),
         Line::from(""),
         Line::from(vec![
             Span::styled("DNS Time:  ", Style::default().fg(app.theme.low)),
@@ -485,7 +614,36 @@ fn draw_statistics(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn draw_jitter_panel(f: &mut Frame, app: &App, area: Rect) {
-    let stats = app.ping_monitor.stats();
+    let stats = app.stats();
+
+    let mos_color = if stats.mos >= 4.0 {
+        app.theme.good
+    } else if stats.mos >= 3.6 {
+        app.theme.warn
+    } else {
+        app.theme.crit
+    };
+
+    let mut verdicts = Vec::new();
+    for use_case in UseCase::ALL {
+        if !verdicts.is_empty() {
+            verdicts.push(Span::raw(" │ "));
+        }
+        let verdict = stats.verdict(use_case);
+        let color = match verdict {
+            Verdict::Good => app.theme.good,
+            Verdict::Fair => app.theme.warn,
+            Verdict::Poor => app.theme.crit,
+        };
+        verdicts.push(Span::styled(
+            format!("{use_case}: "),
+            Style::default().fg(app.theme.low),
+        ));
+        verdicts.push(Span::styled(
+            verdict.to_string(),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+    }
 
     // Calculate gauge color based on stability
     let gauge_color = if stats.stability >= 90.0 {
@@ -498,12 +656,41 @@ fn draw_jitter_panel(f: &mut Frame, app: &App, area: Rect) {
 
     let text = vec![
         Line::from(vec![
-            Span::styled("Jitter:       ", Style::default().fg(app.theme.low)),
+            Span::styled("Jitter (RFC): ", Style::default().fg(app.theme.low)),
             Span::styled(
                 format!("{:>8.1} ms", stats.jitter),
+                Style::default()
+                    .fg(app.theme.fg)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Mean |ΔRTT|:  ", Style::default().fg(app.theme.low)),
+            Span::styled(
+                format!("{:>8.1} ms", stats.mean_delta),
+                Style::default().fg(app.theme.fg),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Std Dev (win):", Style::default().fg(app.theme.low)),
+            Span::styled(
+                format!("{:>8.1} ms", stats.window.std_dev),
                 Style::default().fg(app.theme.fg),
             ),
         ]),
+        Line::from(vec![
+            Span::styled("E-model R:    ", Style::default().fg(app.theme.low)),
+            Span::styled(
+                format!("{:>8.1}", stats.r_factor),
+                Style::default().fg(app.theme.fg),
+            ),
+            Span::styled("  MOS ", Style::default().fg(app.theme.low)),
+            Span::styled(
+                format!("{:.2}", stats.mos),
+                Style::default().fg(mos_color).add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(verdicts),
         Line::from(""),
         Line::from(vec![
             Span::styled("Stability:    ", Style::default().fg(app.theme.low)),
@@ -535,10 +722,13 @@ fn draw_jitter_panel(f: &mut Frame, app: &App, area: Rect) {
 
     f.render_widget(paragraph, area);
 
-    // Draw stability gauge
+    // Draw stability gauge over the "Stability" row when it fits inside the border
+    if area.height < 9 {
+        return;
+    }
     let gauge_area = Rect {
         x: area.x + 1,
-        y: area.y + 3,
+        y: area.y + 7,
         width: area.width.saturating_sub(2),
         height: 1,
     };
@@ -553,8 +743,12 @@ fn draw_jitter_panel(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
+    if app.replay.is_some() {
+        draw_replay_footer(f, app, area);
+        return;
+    }
     let runtime = app.runtime();
-    let stats = app.ping_monitor.stats();
+    let stats = app.stats();
 
     // Enhanced footer with highlighted keys (btop-style)
     let mut spans = vec![
@@ -594,6 +788,30 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled("ort", Style::default().fg(app.theme.low)),
+            Span::raw(" │ "),
+            Span::styled(
+                "[A]",
+                Style::default()
+                    .fg(app.theme.key_highlight)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("dd", Style::default().fg(app.theme.low)),
+            Span::raw(" │ "),
+            Span::styled(
+                "[V]",
+                Style::default()
+                    .fg(app.theme.key_highlight)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("iew", Style::default().fg(app.theme.low)),
+            Span::raw(" │ "),
+            Span::styled(
+                "[T]",
+                Style::default()
+                    .fg(app.theme.key_highlight)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("race", Style::default().fg(app.theme.low)),
         ]);
     } else {
         spans.extend(vec![
@@ -625,7 +843,7 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
         Span::raw(" │ "),
         Span::styled("Pkts: ", Style::default().fg(app.theme.low)),
         Span::styled(
-            format!("{}", stats.total_pings),
+            format!("{}", stats.lifetime.sent),
             Style::default().fg(app.theme.fg),
         ),
         Span::raw(" │ "),
@@ -648,8 +866,80 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
+/// Playback controls and position while replaying a recording.
+fn draw_replay_footer(f: &mut Frame, app: &App, area: Rect) {
+    let Some(player) = &app.replay else {
+        return;
+    };
+    let clock = |d: Duration| {
+        let secs = d.as_secs();
+        format!(
+            "{:02}:{:02}:{:02}",
+            secs / 3600,
+            (secs % 3600) / 60,
+            secs % 60
+        )
+    };
+    let key = |k: &'static str| {
+        Span::styled(
+            k,
+            Style::default()
+                .fg(app.theme.key_highlight)
+                .add_modifier(Modifier::BOLD),
+        )
+    };
+    let label = |l: &'static str| Span::styled(l, Style::default().fg(app.theme.low));
+
+    let mut spans = vec![
+        key("[Q]"),
+        label("uit"),
+        Span::raw(" │ "),
+        key("[Space]"),
+        label(if player.paused { " Play" } else { " Pause" }),
+        Span::raw(" │ "),
+        key("[←→]"),
+        label(" ±10s "),
+        key("[PgUp/PgDn]"),
+        label(" ±1m "),
+        key("[Home]"),
+        label(" Start"),
+        Span::raw(" │ "),
+        key("[1/2/3]"),
+        label(" Speed "),
+    ];
+    for speed in SPEEDS {
+        let style = if speed == player.speed {
+            Style::default()
+                .fg(app.theme.hi_fg)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+        } else {
+            Style::default().fg(app.theme.low)
+        };
+        spans.push(Span::styled(format!("{speed}x"), style));
+        spans.push(Span::raw(" "));
+    }
+    spans.extend([
+        Span::raw("│ "),
+        Span::styled(
+            format!("{} / {}", clock(player.position()), clock(player.length())),
+            Style::default()
+                .fg(app.theme.fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" │ "),
+        Span::styled(
+            player.file().to_string(),
+            Style::default().fg(app.theme.low),
+        ),
+    ]);
+
+    let paragraph =
+        Paragraph::new(Line::from(spans)).style(Style::default().bg(app.theme.bg).fg(app.theme.fg));
+    f.render_widget(paragraph, area);
+}
+
 fn draw_settings_overlay(f: &mut Frame, app: &App) {
-    let area = centered_rect(45, 40, f.area());
+    let area = centered_rect(45, 55, f.area());
 
     // Enhanced settings overlay with better styling
     let text = vec![
@@ -740,6 +1030,25 @@ fn draw_settings_overlay(f: &mut Frame, app: &App) {
             ),
         ]),
         Line::from(""),
+        settings_value_row(
+            app,
+            3,
+            "Probe Timeout",
+            format!("{} ms", app.config.probe_timeout_ms),
+        ),
+        settings_value_row(
+            app,
+            4,
+            "Payload Size",
+            format!("{} bytes", app.config.payload_size),
+        ),
+        settings_value_row(
+            app,
+            5,
+            "Payload Pattern",
+            format!("0x{}", app.config.payload_pattern),
+        ),
+        Line::from(""),
         Line::from(vec![
             Span::styled(
                 " ↑/↓ ",
@@ -748,6 +1057,13 @@ fn draw_settings_overlay(f: &mut Frame, app: &App) {
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled("Navigate  ", Style::default().fg(app.theme.low)),
+            Span::styled(
+                " ←/→ ",
+                Style::default()
+                    .fg(app.theme.hi_fg)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("Adjust  ", Style::default().fg(app.theme.low)),
             Span::styled(
                 " Enter ",
                 Style::default()
@@ -782,6 +1098,33 @@ fn draw_settings_overlay(f: &mut Frame, app: &App) {
     f.render_widget(paragraph, area);
 }
 
+/// A settings line showing an adjustable value as `◀ value ▶`.
+fn settings_value_row(app: &App, index: usize, label: &str, value: String) -> Line<'static> {
+    let selected = app.settings_selected == index;
+    Line::from(vec![
+        Span::styled(
+            if selected { " ▶ " } else { "   " },
+            Style::default().fg(app.theme.title),
+        ),
+        Span::styled(
+            format!("{label}: "),
+            Style::default().fg(if selected {
+                app.theme.hi_fg
+            } else {
+                app.theme.fg
+            }),
+        ),
+        Span::styled(
+            format!("◀ {value} ▶"),
+            Style::default().fg(if selected {
+                app.theme.good
+            } else {
+                app.theme.low
+            }),
+        ),
+    ])
+}
+
 // Helper function to create highlighted key spans (btop-style)
 fn highlighted_key<'a>(theme: &Theme, key: &'a str, label: &'a str) -> Vec<Span<'a>> {
     vec![
@@ -967,7 +1310,7 @@ fn draw_portscan_panel(f: &mut Frame, app: &App, area: Rect) {
 
         let mut lines = vec![
             Line::from(vec![Span::styled(
-                format!("Scanning: {}", app.target),
+                format!("Scanning: {}", app.target()),
                 Style::default()
                     .fg(app.theme.title)
                     .add_modifier(Modifier::BOLD),
@@ -1059,6 +1402,290 @@ fn draw_portscan_panel(f: &mut Frame, app: &App, area: Rect) {
     }
 }
 
+fn draw_overview(f: &mut Frame, app: &App, area: Rect) {
+    // Leave room for borders, header row and the fixed-width columns
+    let trend_width = area.width.saturating_sub(80).clamp(8, 60) as usize;
+
+    let header = Row::new(vec![
+        "#", "Target", "Address", "Now", "Avg", "Min", "Max", "Loss", "Jitter", "Quality", "Trend",
+    ])
+    .style(
+        Style::default()
+            .fg(app.theme.title)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let rows: Vec<Row> = app
+        .targets
+        .iter()
+        .enumerate()
+        .map(|(i, session)| {
+            let stats = &session.stats;
+            let current = match stats.current_response {
+                Some(ms) => Cell::from(format!("{ms:.1}"))
+                    .style(Style::default().fg(app.theme.latency_color(ms))),
+                None => Cell::from("---").style(Style::default().fg(app.theme.crit)),
+            };
+            let loss_color = if stats.lifetime.loss_pct > 0.0 {
+                app.theme.crit
+            } else {
+                app.theme.fg
+            };
+            let row = Row::new(vec![
+                Cell::from(format!("{}", i + 1)),
+                Cell::from(session.target.clone()),
+                Cell::from(session.ping_monitor.get_target_addr().to_string())
+                    .style(Style::default().fg(app.theme.low)),
+                current,
+                Cell::from(format!("{:.1}", stats.window.avg_ms)),
+                Cell::from(format!("{:.1}", stats.window.min_ms)),
+                Cell::from(format!("{:.1}", stats.window.max_ms)),
+                Cell::from(format!("{:.1}%", stats.lifetime.loss_pct))
+                    .style(Style::default().fg(loss_color)),
+                Cell::from(format!("{:.1}", stats.jitter)),
+                Cell::from(stats.quality.clone())
+                    .style(Style::default().fg(app.theme.quality_color(&stats.quality))),
+                Cell::from(sparkline_text(
+                    session.ping_monitor.latency_data(),
+                    trend_width,
+                ))
+                .style(Style::default().fg(app.theme.graph_low)),
+            ]);
+            if i == app.selected_target {
+                row.style(
+                    Style::default()
+                        .bg(app.theme.selected_bg)
+                        .fg(app.theme.selected_fg),
+                )
+            } else {
+                row
+            }
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Min(12),
+        Constraint::Length(16),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(10),
+        Constraint::Length(trend_width as u16),
+    ];
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .title(format!(
+                    " OVERVIEW │ {} targets │ Avg/Min/Max: graph window, Loss: session ",
+                    app.targets.len()
+                ))
+                .title_style(
+                    Style::default()
+                        .fg(app.theme.title)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.theme.box_color)),
+        )
+        .style(Style::default().bg(app.theme.bg).fg(app.theme.fg));
+
+    f.render_widget(table, area);
+}
+
+fn draw_traceroute(f: &mut Frame, app: &App, area: Rect) {
+    let Some(trace) = &app.traceroute else {
+        return;
+    };
+
+    let block = Block::default()
+        .title(format!(
+            " TRACEROUTE │ {} ({}) │ {} ",
+            trace.target,
+            trace.target_addr,
+            if trace.reached() {
+                format!("{} hops", trace.hops().len())
+            } else {
+                "tracing...".to_string()
+            }
+        ))
+        .title_style(
+            Style::default()
+                .fg(app.theme.title)
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(app.theme.box_color));
+
+    if let Some(error) = &trace.error {
+        let paragraph = Paragraph::new(vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("  {error}"),
+                Style::default().fg(app.theme.crit),
+            )),
+        ])
+        .block(block)
+        .style(Style::default().bg(app.theme.bg).fg(app.theme.fg));
+        f.render_widget(paragraph, area);
+        return;
+    }
+
+    let history_width = area.width.saturating_sub(75).clamp(8, 60) as usize;
+    let header = Row::new(vec![
+        "Hop", "Host", "Loss", "Sent", "Last", "Avg", "Best", "Worst", "History",
+    ])
+    .style(
+        Style::default()
+            .fg(app.theme.title)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let ms = |value: Option<f64>| value.map_or_else(|| "---".to_string(), |v| format!("{v:.1}"));
+    let rows: Vec<Row> = trace
+        .hops()
+        .iter()
+        .enumerate()
+        .map(|(i, hop)| {
+            let loss = hop.loss_pct();
+            let loss_color = if loss >= 100.0 {
+                app.theme.missed
+            } else if loss > 0.0 {
+                app.theme.crit
+            } else {
+                app.theme.fg
+            };
+            let avg = hop.avg_ms();
+            Row::new(vec![
+                Cell::from(format!("{:>2}", i + 1)),
+                Cell::from(
+                    hop.addr
+                        .map_or_else(|| "???".to_string(), |a| a.to_string()),
+                ),
+                Cell::from(format!("{loss:.1}%")).style(Style::default().fg(loss_color)),
+                Cell::from(hop.sent.to_string()),
+                Cell::from(ms(hop.last_ms)),
+                Cell::from(ms(avg)).style(
+                    Style::default().fg(avg.map_or(app.theme.low, |v| app.theme.latency_color(v))),
+                ),
+                Cell::from(ms((hop.received > 0).then_some(hop.best_ms))),
+                Cell::from(ms((hop.received > 0).then_some(hop.worst_ms))),
+                Cell::from(sparkline_text(&hop.history, history_width))
+                    .style(Style::default().fg(app.theme.graph_low)),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Min(16),
+        Constraint::Length(7),
+        Constraint::Length(6),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(history_width as u16),
+    ];
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .style(Style::default().bg(app.theme.bg).fg(app.theme.fg));
+    f.render_widget(table, area);
+}
+
+/// Render the newest `width` samples as a one-line block-character sparkline.
+/// Timeouts are shown as `·` so outages stay visible in the compact view.
+fn sparkline_text(data: &std::collections::VecDeque<Option<f64>>, width: usize) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let skip = data.len().saturating_sub(width);
+    let max = data
+        .iter()
+        .skip(skip)
+        .filter_map(|&v| v)
+        .fold(0.0_f64, f64::max)
+        .max(1.0);
+    data.iter()
+        .skip(skip)
+        .map(|v| match v {
+            Some(ms) => LEVELS[((ms / max) * (LEVELS.len() - 1) as f64).round() as usize],
+            None => '·',
+        })
+        .collect()
+}
+
+fn draw_add_target_overlay(f: &mut Frame, app: &App) {
+    let area = centered_rect(50, 25, f.area());
+
+    let mut text = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(" Target: ", Style::default().fg(app.theme.low)),
+            Span::styled(
+                format!("{}_", app.add_target_input),
+                Style::default()
+                    .fg(app.theme.hi_fg)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    if let Some(target) = app.adding_target() {
+        text.push(Line::from(Span::styled(
+            format!(" Resolving {target}..."),
+            Style::default().fg(app.theme.low),
+        )));
+        text.push(Line::from(""));
+    } else if let Some(err) = &app.add_target_error {
+        text.push(Line::from(Span::styled(
+            format!(" {err}"),
+            Style::default().fg(app.theme.crit),
+        )));
+        text.push(Line::from(""));
+    }
+
+    text.push(Line::from(vec![
+        Span::styled(
+            " Enter ",
+            Style::default()
+                .fg(app.theme.hi_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("Add  ", Style::default().fg(app.theme.low)),
+        Span::styled(
+            " ESC ",
+            Style::default()
+                .fg(app.theme.hi_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("Cancel", Style::default().fg(app.theme.low)),
+    ]));
+
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .title(" ADD TARGET ")
+                .title_style(
+                    Style::default()
+                        .fg(app.theme.title)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.theme.title)),
+        )
+        .style(Style::default().bg(app.theme.selected_bg).fg(app.theme.fg));
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
 fn format_web_check(status: &WebCheckStatus) -> String {
     match status {
         WebCheckStatus::Untested => "Not Run".to_string(),
@@ -1069,9 +1696,57 @@ fn format_web_check(status: &WebCheckStatus) -> String {
     }
 }
 
+fn anomaly_count(app: &App, count: u64) -> Span<'static> {
+    let color = if count > 0 {
+        app.theme.warn
+    } else {
+        app.theme.fg
+    };
+    Span::styled(count.to_string(), Style::default().fg(color))
+}
+
+/// One-line outage summary for the statistics panel.
+fn outage_line<'a>(app: &App, outages: &OutageSummary) -> Line<'a> {
+    let label = Span::styled("  Outages:", Style::default().fg(app.theme.low));
+    if outages.count == 0 {
+        return Line::from(vec![
+            label,
+            Span::styled("       0", Style::default().fg(app.theme.good)),
+        ]);
+    }
+    let mut spans = vec![
+        label,
+        Span::styled(
+            format!("{:>8}", outages.count),
+            Style::default()
+                .fg(app.theme.crit)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("  max {}", format_duration(outages.longest)),
+            Style::default().fg(app.theme.fg),
+        ),
+    ];
+    if let Some(mttr) = outages.mttr {
+        spans.push(Span::styled(
+            format!("  MTTR {}", format_duration(mttr)),
+            Style::default().fg(app.theme.fg),
+        ));
+    }
+    if let Some(ongoing) = outages.ongoing {
+        spans.push(Span::styled(
+            format!("  DOWN {}", format_duration(ongoing)),
+            Style::default()
+                .fg(app.theme.missed)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    Line::from(spans)
+}
+
 fn draw_diagnostics_overlay(f: &mut Frame, app: &App) {
-    let area = centered_rect(60, 50, f.area());
-    let stats = app.ping_monitor.stats();
+    let area = centered_rect(60, 60, f.area());
+    let stats = app.stats();
 
     let dns_status = if let Some(ms) = stats.dns_duration {
         format!("{ms:.2} ms")
@@ -1079,6 +1754,23 @@ fn draw_diagnostics_overlay(f: &mut Frame, app: &App) {
         "N/A".to_string()
     };
 
+    let (mtu_status, mtu_color) = match &stats.path_mtu {
+        PathMtu::Unknown => ("Press M to discover".to_string(), app.theme.low),
+        PathMtu::Discovering => ("Discovering...".to_string(), app.theme.warn),
+        PathMtu::Found { mtu, .. } => (format!("{mtu} bytes"), app.theme.hi_fg),
+        PathMtu::Failed(e) => (format!("Failed: {e}"), app.theme.missed),
+    };
+
+    let monitor = app.ping_monitor();
+    let probe_status = if monitor.probe_fell_back() {
+        format!(
+            "{} (fallback from {})",
+            monitor.probe_method, monitor.requested_probe_method
+        )
+    } else {
+        monitor.probe_method.to_string()
+    };
+
     let http_status = format_web_check(&stats.tcp_port_80);
     let https_status = format_web_check(&stats.tcp_port_443);
 
@@ -1094,7 +1786,14 @@ fn draw_diagnostics_overlay(f: &mut Frame, app: &App) {
         _ => app.theme.missed,
     };
 
-    let text = vec![
+    let ttl_status = stats
+        .ttl
+        .map_or_else(|| "N/A".to_string(), |ttl| ttl.to_string());
+    let size_status = stats
+        .reply_size
+        .map_or_else(|| "N/A".to_string(), |size| format!("{size} bytes"));
+
+    let mut text = vec![
         Line::from(""),
         Line::from(Span::styled(
             "Network Intelligence",
@@ -1106,6 +1805,19 @@ fn draw_diagnostics_overlay(f: &mut Frame, app: &App) {
         Line::from(vec![
             Span::raw("DNS Resolution: "),
             Span::styled(dns_status, Style::default().fg(app.theme.hi_fg)),
+            Span::raw("  Path MTU: "),
+            Span::styled(mtu_status, Style::default().fg(mtu_color)),
+        ]),
+        Line::from(vec![
+            Span::raw("Probe Method: "),
+            Span::styled(
+                probe_status,
+                Style::default().fg(if app.ping_monitor().probe_fell_back() {
+                    app.theme.warn
+                } else {
+                    app.theme.hi_fg
+                }),
+            ),
         ]),
         Line::from(""),
         Line::from(Span::styled(
@@ -1122,33 +1834,106 @@ fn draw_diagnostics_overlay(f: &mut Frame, app: &App) {
         ]),
         Line::from(""),
         Line::from(Span::styled(
-            "ICMP Statistics",
+            format!("{} Statistics", app.ping_monitor().probe_method),
             Style::default().fg(app.theme.title),
         )),
         Line::from(vec![
             Span::raw("Total Pings: "),
             Span::styled(
-                format!("{}", stats.total_pings),
+                format!("{}", stats.lifetime.sent),
                 Style::default().fg(app.theme.fg),
             ),
         ]),
         Line::from(vec![
             Span::raw("Packet Loss: "),
             Span::styled(
-                format!("{:.1}%", stats.packet_loss_pct),
-                Style::default().fg(if stats.packet_loss_pct > 0.0 {
+                format!("{:.1}%", stats.lifetime.loss_pct),
+                Style::default().fg(if stats.lifetime.loss_pct > 0.0 {
                     app.theme.missed
                 } else {
                     app.theme.good
                 }),
             ),
         ]),
-        Line::from(""),
-        Line::from(Span::styled(
-            "Press ESC to close",
-            Style::default().fg(app.theme.low),
-        )),
+        Line::from(vec![
+            Span::raw("Reply TTL: "),
+            Span::styled(ttl_status, Style::default().fg(app.theme.fg)),
+            Span::raw("  Reply Size: "),
+            Span::styled(size_status, Style::default().fg(app.theme.fg)),
+        ]),
+        Line::from(vec![
+            Span::raw("Route Changes (TTL): "),
+            Span::styled(
+                stats.route_changes.to_string(),
+                Style::default().fg(if stats.route_changes > 0 {
+                    app.theme.warn
+                } else {
+                    app.theme.fg
+                }),
+            ),
+        ]),
+        Line::from(vec![
+            Span::raw("Late: "),
+            anomaly_count(app, stats.late_replies),
+            Span::raw("  Duplicate: "),
+            anomaly_count(app, stats.duplicate_replies),
+            Span::raw("  Out of Order: "),
+            anomaly_count(app, stats.reordered_replies),
+        ]),
     ];
+    if let Some(source) = stats.last_foreign_source {
+        text.push(Line::from(Span::styled(
+            format!(
+                "Warning: {} replies from {} instead of the target",
+                stats.foreign_replies, source
+            ),
+            Style::default().fg(app.theme.warn),
+        )));
+    }
+    if let Some(log) = &app.log {
+        text.push(Line::from(Span::styled(
+            log.describe(),
+            Style::default().fg(app.theme.low),
+        )));
+    }
+    let outages = monitor.outage_list();
+    if !outages.is_empty() {
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            format!(
+                "Outages ({} consecutive timeouts)",
+                monitor.outages.threshold
+            ),
+            Style::default().fg(app.theme.title),
+        )));
+        let now = app.now();
+        // The most recent outages fit; the summary in the statistics panel covers all
+        for outage in outages.iter().rev().take(5) {
+            let end = outage.end.map_or_else(
+                || "ongoing".to_string(),
+                |end| end.format("%H:%M:%S").to_string(),
+            );
+            text.push(Line::from(Span::styled(
+                format!(
+                    "{} - {}  {}  ({} lost)",
+                    outage.start.format("%H:%M:%S"),
+                    end,
+                    format_duration(outage.duration(now)),
+                    outage.missed
+                ),
+                Style::default().fg(if outage.end.is_some() {
+                    app.theme.fg
+                } else {
+                    app.theme.missed
+                }),
+            )));
+        }
+    }
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        "Press ESC to close",
+        Style::default().fg(app.theme.low),
+    )));
 
     let block = Block::default()
         .borders(Borders::ALL)
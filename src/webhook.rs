@@ -0,0 +1,314 @@
+use crate::alerts::{AlertEvent, AlertState};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// Where and how alert events are POSTed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub format: WebhookFormat,
+    /// Extra attempts after a failed delivery
+    pub retries: u32,
+    /// Window in which repeated events for the same target and rule are coalesced
+    pub dedup_secs: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            format: WebhookFormat::Generic,
+            retries: 3,
+            dedup_secs: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    Slack,
+    Teams,
+    Mattermost,
+    /// Flat JSON object with every event field
+    #[default]
+    Generic,
+}
+
+/// Build the request body for `event` in the given format.
+pub fn payload(format: WebhookFormat, event: &AlertEvent) -> Value {
+    let (icon, color) = match event.state {
+        AlertState::Firing => ("🔴", "D70000"),
+        AlertState::Resolved => ("✅", "2EB886"),
+    };
+    let text = format!("{icon} [rping] {}: {}", event.target, event.message);
+    match format {
+        WebhookFormat::Slack => json!({ "text": text }),
+        WebhookFormat::Mattermost => json!({ "text": text, "username": "rping" }),
+        WebhookFormat::Teams => json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "themeColor": color,
+            "summary": text,
+            "title": format!("rping alert {}: {}", event.state, event.target),
+            "text": event.message,
+        }),
+        WebhookFormat::Generic => json!({
+            "source": "rping",
+            "target": event.target,
+            "rule": event.rule.name(),
+            "state": event.state,
+            "message": event.message,
+            "value": event.value,
+            "timestamp": event.at.to_rfc3339(),
+        }),
+    }
+}
+
+/// Per target and rule: when an event was last delivered, and the newest
+/// event held back since then.
+struct DedupEntry {
+    sent_at: Instant,
+    sent_state: AlertState,
+    pending: Option<AlertEvent>,
+}
+
+/// Rate limits events per target and rule. Within the window repeats are
+/// dropped and a flapping rule is reduced to its latest state, which is sent
+/// once the window has passed.
+pub struct Dedup {
+    window: Duration,
+    entries: HashMap<(String, &'static str), DedupEntry>,
+}
+
+impl Dedup {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Returns the event if it should be delivered now.
+    pub fn offer(&mut self, event: AlertEvent, now: Instant) -> Option<AlertEvent> {
+        let key = (event.target.clone(), event.rule.name());
+        match self.entries.get_mut(&key) {
+            Some(entry) if now.duration_since(entry.sent_at) < self.window => {
+                // A flap back to the delivered state cancels what was held
+                entry.pending = (event.state != entry.sent_state).then_some(event);
+                None
+            }
+            _ => {
+                self.entries.insert(
+                    key,
+                    DedupEntry {
+                        sent_at: now,
+                        sent_state: event.state,
+                        pending: None,
+                    },
+                );
+                Some(event)
+            }
+        }
+    }
+
+    /// Held-back events whose window has passed.
+    pub fn due(&mut self, now: Instant) -> Vec<AlertEvent> {
+        let mut due = Vec::new();
+        for entry in self.entries.values_mut() {
+            if now.duration_since(entry.sent_at) < self.window {
+                continue;
+            }
+            if let Some(event) = entry.pending.take() {
+                entry.sent_at = now;
+                entry.sent_state = event.state;
+                due.push(event);
+            }
+        }
+        due
+    }
+}
+
+/// Handle to the background task delivering alert events to a webhook.
+pub struct WebhookNotifier {
+    tx: mpsc::Sender<AlertEvent>,
+}
+
+impl WebhookNotifier {
+    pub fn start(config: WebhookConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let (tx, mut rx) = mpsc::channel::<AlertEvent>(64);
+        info!("Sending alerts to webhook ({:?})", config.format);
+
+        tokio::spawn(async move {
+            let mut dedup = Dedup::new(Duration::from_secs(config.dedup_secs));
+            let mut flush = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                let ready = tokio::select! {
+                    event = rx.recv() => match event {
+                        Some(event) => dedup.offer(event, Instant::now()).into_iter().collect(),
+                        None => break,
+                    },
+                    _ = flush.tick() => dedup.due(Instant::now()),
+                };
+                for event in ready {
+                    deliver(&client, &config, &event).await;
+                }
+            }
+            debug!("Webhook task stopped");
+        });
+
+        Ok(Self { tx })
+    }
+
+    /// Queue an event without blocking the UI.
+    pub fn notify(&self, event: &AlertEvent) {
+        if let Err(e) = self.tx.try_send(event.clone()) {
+            warn!("Dropping webhook alert for {}: {}", event.target, e);
+        }
+    }
+}
+
+/// POST one event, retrying with exponential backoff.
+async fn deliver(client: &reqwest::Client, config: &WebhookConfig, event: &AlertEvent) {
+    let body = payload(config.format, event).to_string();
+    let mut backoff = Duration::from_millis(500);
+    for attempt in 0..=config.retries {
+        let result = client
+            .post(&config.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => {
+                debug!("Webhook delivered: {} {}", event.target, event.message);
+                return;
+            }
+            Err(e) if attempt < config.retries => {
+                warn!("Webhook attempt {} failed: {}", attempt + 1, e);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(Duration::from_secs(30));
+            }
+            Err(e) => error!("Giving up on webhook after {} attempts: {}", attempt + 1, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertRule;
+    use chrono::Local;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn event(state: AlertState) -> AlertEvent {
+        AlertEvent {
+            target: "example.com".to_string(),
+            rule: AlertRule::Outage,
+            state,
+            message: "Outage".to_string(),
+            value: None,
+            at: Local::now(),
+        }
+    }
+
+    #[test]
+    fn test_dedup_coalesces_flapping() {
+        let mut dedup = Dedup::new(Duration::from_secs(60));
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        assert!(dedup.offer(event(AlertState::Firing), at(0)).is_some());
+        // Flapping inside the window: nothing is sent right away
+        assert!(dedup.offer(event(AlertState::Resolved), at(5)).is_none());
+        assert!(dedup.offer(event(AlertState::Firing), at(10)).is_none());
+        assert!(dedup.offer(event(AlertState::Resolved), at(15)).is_none());
+        assert!(dedup.due(at(30)).is_empty());
+
+        // Only the final state goes out once the window has passed
+        let due = dedup.due(at(61));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].state, AlertState::Resolved);
+        assert!(dedup.due(at(200)).is_empty());
+    }
+
+    /// Minimal HTTP server: answers each request with the next status code
+    /// and forwards the request bodies.
+    async fn stand_in(statuses: Vec<u16>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel(8);
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                let body = loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|l| {
+                                l.to_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break body.to_string();
+                        }
+                    }
+                };
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                tx.send(body).await.unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries_until_accepted() {
+        let (url, mut bodies) = stand_in(vec![503, 200]).await;
+        let notifier = WebhookNotifier::start(WebhookConfig {
+            url,
+            format: WebhookFormat::Slack,
+            ..WebhookConfig::default()
+        })
+        .unwrap();
+        notifier.notify(&event(AlertState::Firing));
+
+        for _ in 0..2 {
+            let body = tokio::time::timeout(Duration::from_secs(5), bodies.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            let json: Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(json["text"], "🔴 [rping] example.com: Outage");
+        }
+    }
+
+    #[test]
+    fn test_generic_payload() {
+        let json = payload(WebhookFormat::Generic, &event(AlertState::Resolved));
+        assert_eq!(json["rule"], "outage");
+        assert_eq!(json["state"], "resolved");
+        assert_eq!(json["target"], "example.com");
+    }
+}